@@ -42,7 +42,7 @@ fn get_argon2() -> &'static Argon2<'static> {
     INSTANCE.get_or_init(|| {
         let key = &auth_config().PWD_KEY;
         Argon2::new_with_secret(
-            &key,
+            key,
             argon2::Algorithm::Argon2id,
             argon2::Version::V0x13,
             Params::default(),
@@ -1,15 +1,14 @@
-use std::{
-    fs,
-    path::{Path, PathBuf},
-    time::Duration,
-};
+use std::{fs, path::PathBuf, time::Duration};
 
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 use tracing::debug;
 
 use crate::{
+    core_config,
     ctx::Ctx,
     model::{
+        migrate::Migrator,
+        script,
         user::{User, UserBmc},
         ModelManager,
     },
@@ -46,21 +45,26 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
     // Create the app_db/ app_user with the postgres(root) user.
     {
         let root_db = new_db_pool(PG_DEV_POSTGRES_URL).await?;
-        pexec(&root_db, &sql_dir.join(SQL_RECREATE_DB)).await?;
+        script::exec_file(&root_db, &sql_dir.join(SQL_RECREATE_DB)).await?;
     }
 
+    // Create the schema with the migrations.
+    let app_db = new_db_pool(PG_DEV_APP_URL).await?;
+    Migrator::new(app_db.clone(), &core_config().DB_MIGRATIONS_DIR)?
+        .up()
+        .await?;
+
     let mut paths: Vec<_> = fs::read_dir(sql_dir)?
         .filter_map(|entry| entry.ok().map(|e| e.path()))
         .collect();
     paths.sort();
 
-    // SQL Execute each file.
-    let app_db = new_db_pool(PG_DEV_APP_URL).await?;
+    // SQL Execute each seed file.
     for path in paths {
         let path_str = path.to_string_lossy();
 
         if path_str.ends_with(".sql") && !path_str.ends_with(SQL_RECREATE_DB) {
-            pexec(&app_db, &path).await?;
+            script::exec_file_in_txn(&app_db, &path).await?;
         }
     }
 
@@ -77,22 +81,6 @@ pub async fn init_dev_db() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn pexec(db: &Db, file: &Path) -> Result<(), sqlx::Error> {
-    debug!("{:<12} - pexec: {file:?}", "FOR-DEV-ONLY");
-
-    // Read the file.
-    let content = fs::read_to_string(file)?;
-
-    // FIXME: Make the split more sql proof.
-    let sqls: Vec<_> = content.split(';').collect();
-
-    for sql in sqls {
-        sqlx::query(sql).execute(db).await?;
-    }
-
-    Ok(())
-}
-
 async fn new_db_pool(db_con_url: &str) -> Result<Db, sqlx::Error> {
     debug!("{:<12} - new_db_pool", "FOR-DEV-ONLY");
     PgPoolOptions::new()
@@ -7,6 +7,7 @@ use crate::{
     ctx::Ctx,
     model::{
         self,
+        project::{ProjectBmc, ProjectForCreate},
         task::{Task, TaskBmc, TaskForCreate},
         ModelManager,
     },
@@ -37,9 +38,27 @@ pub async fn init_test() -> ModelManager {
     mm.clone()
 }
 
+/// A helper function that uses ProjectBmc to add a `Project` named `name` to the DB and returns
+/// its id, or an error if encountered.
+pub async fn seed_project(ctx: &Ctx, mm: &ModelManager, name: &str) -> model::Result<i64> {
+    ProjectBmc::create(
+        ctx,
+        mm,
+        ProjectForCreate {
+            name: name.to_string(),
+        },
+    )
+    .await
+}
+
 /// A helper function that uses TaskBmc to add an array of `Task`s created from `titles` to the
-/// DB and returns an array of `Task`s that were created, or an error if encountered.
-pub async fn seed_tasks(ctx: &Ctx, mm: &ModelManager, titles: &[&str]) -> model::Result<Vec<Task>> {
+/// project with `project_id` and returns an array of `Task`s that were created, or an error if encountered.
+pub async fn seed_tasks(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    project_id: i64,
+    titles: &[&str],
+) -> model::Result<Vec<Task>> {
     let mut tasks = Vec::with_capacity(titles.len());
     for title in titles {
         let id = TaskBmc::create(
@@ -47,6 +66,8 @@ pub async fn seed_tasks(ctx: &Ctx, mm: &ModelManager, titles: &[&str]) -> model:
             mm,
             TaskForCreate {
                 title: title.to_string(),
+                project_id,
+                ..Default::default()
             },
         )
         .await?;
@@ -15,15 +15,14 @@ pub fn core_config() -> &'static CoreConfig {
 pub struct CoreConfig {
     // Db
     pub DB_URL: String,
-    // Web
-    pub WEB_FOLDER: String,
+    pub DB_MIGRATIONS_DIR: String,
 }
 
 impl CoreConfig {
     fn load_from_env() -> lib_utils::envs::Result<CoreConfig> {
         Ok(CoreConfig {
             DB_URL: get_env("SERVICE_DB_URL")?,
-            WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
+            DB_MIGRATIONS_DIR: get_env("SERVICE_DB_MIGRATIONS_DIR")?,
         })
     }
 }
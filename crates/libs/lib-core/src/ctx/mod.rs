@@ -26,4 +26,9 @@ impl Ctx {
     pub fn user_id(&self) -> i64 {
         self.user_id
     }
+
+    /// Returns `true` for the root `Ctx`, which bypasses the ownership checks of the model layer.
+    pub fn is_root(&self) -> bool {
+        self.user_id == 0
+    }
 }
@@ -0,0 +1,286 @@
+//! In-process dispatcher of the domain events of the outbox (see `model::outbox`).
+//!
+//! - The `Dispatcher` polls the pending events, oldest first, delivers each of them to all its
+//!   subscribers, and marks it processed.
+//! - Delivery is at least once: the events are marked processed in the transaction locking
+//!   them, once delivered. An event failing in a subscriber (or not marked because of a crash)
+//!   is delivered again to all the subscribers on a later poll, up to `outbox::ATTEMPTS_MAX`
+//!   times. Subscribers must therefore be idempotent (e.g., by the event `id`).
+//! - The pending events are locked with `FOR UPDATE SKIP LOCKED`, so several dispatchers
+//!   (e.g., one per server instance) never deliver the same event concurrently.
+//!
+//! ```ignore
+//! Dispatcher::new(mm)
+//!     .subscribe("log", |event: Arc<Event>| async move {
+//!         info!("{} {}", event.name, event.entity_id);
+//!         Ok::<_, String>(())
+//!     })
+//!     .spawn();
+//! ```
+
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use crate::model::outbox::{Event, OutboxBmc};
+use crate::model::{ModelManager, Result};
+
+const POLL_INTERVAL_DEFAULT: Duration = Duration::from_secs(1);
+const BATCH_SIZE_DEFAULT: u64 = 100;
+
+type PinFutureDelivery = Pin<Box<dyn Future<Output = core::result::Result<(), String>> + Send>>;
+
+struct Subscriber {
+    name: &'static str,
+    handler: Box<dyn Fn(Arc<Event>) -> PinFutureDelivery + Send + Sync>,
+}
+
+pub struct Dispatcher {
+    mm: ModelManager,
+    subscribers: Vec<Subscriber>,
+    poll_interval: Duration,
+    batch_size: u64,
+}
+
+impl Dispatcher {
+    pub fn new(mm: ModelManager) -> Self {
+        Dispatcher {
+            mm,
+            subscribers: Vec::new(),
+            poll_interval: POLL_INTERVAL_DEFAULT,
+            batch_size: BATCH_SIZE_DEFAULT,
+        }
+    }
+
+    /// Adds the async `handler` receiving all the events.
+    /// A handler error gets the event delivered again later (see module doc).
+    pub fn subscribe<F, Fut, E>(mut self, name: &'static str, handler: F) -> Self
+    where
+        F: Fn(Arc<Event>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = core::result::Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        let handler = move |event| {
+            let fut = handler(event);
+            Box::pin(async move { fut.await.map_err(|err| err.to_string()) }) as PinFutureDelivery
+        };
+        self.subscribers.push(Subscriber {
+            name,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Sets the wait between two polls when there are no more pending events.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Delivers the next batch of pending events, and returns the number of events
+    /// processed (the failed ones not counted).
+    pub async fn dispatch_pending(&self) -> Result<usize> {
+        let mm_txn = self.mm.new_with_txn();
+        let events = OutboxBmc::lock_pending(&mm_txn, self.batch_size).await?;
+
+        let mut processed_ids = Vec::new();
+        for event in events {
+            let event = Arc::new(event);
+            match self.deliver(event.clone()).await {
+                Ok(()) => processed_ids.push(event.id),
+                Err(err) => {
+                    warn!(
+                        "{:<12} - event {} ({}) failed - {err}",
+                        "DISPATCHER", event.id, event.name
+                    );
+                    OutboxBmc::mark_failed(&mm_txn, event.id, &err).await?;
+                }
+            }
+        }
+        OutboxBmc::mark_processed(&mm_txn, &processed_ids).await?;
+        mm_txn.commit_txn().await?;
+
+        Ok(processed_ids.len())
+    }
+
+    /// Delivers the event to all the subscribers, and returns the errors of the failed ones.
+    async fn deliver(&self, event: Arc<Event>) -> core::result::Result<(), String> {
+        let mut errors = Vec::new();
+        for subscriber in &self.subscribers {
+            if let Err(err) = (subscriber.handler)(event.clone()).await {
+                errors.push(format!("{}: {err}", subscriber.name));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Runs the dispatcher in a background task, polling until the runtime shuts down.
+    pub fn spawn(self) -> JoinHandle<()> {
+        debug!(
+            "{:<12} - spawn with {} subscriber(s)",
+            "DISPATCHER",
+            self.subscribers.len()
+        );
+        tokio::spawn(async move {
+            loop {
+                match self.dispatch_pending().await {
+                    // A full batch, more events may be pending.
+                    Ok(count) if count as u64 == self.batch_size => continue,
+                    Ok(_) => (),
+                    Err(err) => error!("{:<12} - dispatch_pending - {err:?}", "DISPATCHER"),
+                }
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        })
+    }
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{self, seed_project, seed_tasks};
+    use crate::ctx::Ctx;
+    use crate::model::project::ProjectBmc;
+    use crate::model::task::{TaskBmc, TaskForUpdate};
+    use anyhow::Result;
+    use serial_test::serial;
+    use std::sync::Mutex;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_dispatch_pending_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        // Deliver the events of the previous tests.
+        while Dispatcher::new(mm.clone()).dispatch_pending().await? > 0 {}
+
+        let fx_project_id = seed_project(&ctx, &mm, "test_dispatch_pending_ok project").await?;
+        let fx_task = seed_tasks(&ctx, &mm, fx_project_id, &["task 01"])
+            .await?
+            .remove(0);
+        let task_u = TaskForUpdate {
+            done: Some(true),
+            ..Default::default()
+        };
+        TaskBmc::update(&ctx, &mm, fx_task.id, task_u).await?;
+
+        let received: Arc<Mutex<Vec<String>>> = Arc::default();
+        let failures = Arc::new(Mutex::new(1));
+        let dispatcher = {
+            let received = received.clone();
+            Dispatcher::new(mm.clone()).subscribe("test", move |event: Arc<Event>| {
+                let received = received.clone();
+                let failures = failures.clone();
+                async move {
+                    // Fails the first delivery of the `TaskUpdated` event.
+                    let mut failures = failures.lock().unwrap();
+                    if event.name == "TaskUpdated" && *failures > 0 {
+                        *failures -= 1;
+                        return Err("test failure");
+                    }
+                    received
+                        .lock()
+                        .unwrap()
+                        .push(format!("{} {}", event.name, event.entity_id));
+                    Ok(())
+                }
+            })
+        };
+
+        // -- Exec
+        let first_count = dispatcher.dispatch_pending().await?;
+        let second_count = dispatcher.dispatch_pending().await?;
+        let third_count = dispatcher.dispatch_pending().await?;
+
+        // -- Check
+        assert_eq!(first_count, 2);
+        assert_eq!(second_count, 1);
+        assert_eq!(third_count, 0);
+        let received = received.lock().unwrap().clone();
+        assert_eq!(
+            received,
+            [
+                format!("ProjectCreated {fx_project_id}"),
+                format!("TaskCreated {}", fx_task.id),
+                format!("TaskUpdated {}", fx_task.id),
+            ]
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Dispatcher::new(mm).dispatch_pending().await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_dispatch_pending_unassigned_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let assignee_ctx = Ctx::new(1000)?;
+        // Deliver the events of the previous tests.
+        while Dispatcher::new(mm.clone()).dispatch_pending().await? > 0 {}
+
+        let fx_project_id = seed_project(
+            &root_ctx,
+            &mm,
+            "test_dispatch_pending_unassigned_ok project",
+        )
+        .await?;
+        let fx_task = seed_tasks(&root_ctx, &mm, fx_project_id, &["task 01"])
+            .await?
+            .remove(0);
+        TaskBmc::assign(&root_ctx, &mm, fx_task.id, Some(assignee_ctx.user_id())).await?;
+        TaskBmc::assign(&root_ctx, &mm, fx_task.id, None).await?;
+
+        let received: Arc<Mutex<Vec<String>>> = Arc::default();
+        let dispatcher = {
+            let received = received.clone();
+            Dispatcher::new(mm.clone()).subscribe("test", move |event: Arc<Event>| {
+                let received = received.clone();
+                let assignee_ctx = assignee_ctx.clone();
+                async move {
+                    if event.is_visible_to(&assignee_ctx) {
+                        received
+                            .lock()
+                            .unwrap()
+                            .push(format!("{} {}", event.name, event.entity_id));
+                    }
+                    Ok::<_, String>(())
+                }
+            })
+        };
+
+        // -- Exec
+        dispatcher.dispatch_pending().await?;
+
+        // -- Check: the assign and unassign events, not the ones before the assignment.
+        let received = received.lock().unwrap().clone();
+        assert_eq!(
+            received,
+            [
+                format!("TaskUpdated {}", fx_task.id),
+                format!("TaskUpdated {}", fx_task.id),
+            ]
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&root_ctx, &mm, fx_project_id).await?;
+        Dispatcher::new(mm).dispatch_pending().await?;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 mod config;
 pub mod ctx;
+pub mod event;
 pub mod model;
 //#[cfg(test)] // Commented during early dev.
 pub mod _dev_utils;
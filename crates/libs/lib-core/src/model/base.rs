@@ -1,25 +1,112 @@
+use std::collections::HashMap;
+
+use lib_utils::time::now_utc;
 use modql::{
-    field::HasFields,
-    filter::{FilterGroups, ListOptions},
+    field::{Field, Fields, HasFields},
+    filter::{FilterGroups, ListOptions, OrderBy},
     SIden,
 };
-use sea_query::{Condition, Expr, Iden, IntoIden, PostgresQueryBuilder, Query, TableRef};
+use sea_query::{
+    Alias, Asterisk, Condition, DynIden, Expr, Iden, IntoIden, OnConflict, Order,
+    PostgresQueryBuilder, Query, SimpleExpr, TableRef,
+};
 use sea_query_binder::SqlxBinder;
-use sqlx::{postgres::PgRow, FromRow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{postgres::PgRow, FromRow, Row};
+use time::OffsetDateTime;
 
 use crate::{
     ctx::Ctx,
     model::{Error, Result},
 };
 
+use super::history;
+use super::list_cursor::{Keyed, SortKey, KEY_COLUMN};
+use super::outbox;
 use super::ModelManager;
 
 const LIST_LIMIT_DEFAULT: i64 = 300;
 const LIST_LIMIT_MAX: i64 = 1000;
+const BULK_MAX: usize = 1000;
+
+/// The text search configuration of the `search_vector` columns.
+const SEARCH_CONFIG: &str = "english";
+const SEARCH_SNIPPET_OPTIONS: &str = "StartSel=<b>, StopSel=</b>, MaxFragments=2";
+/// The (SQL literal) replacements HTML escaping the snippet text, `&` first.
+const SEARCH_SNIPPET_ESCAPES: &[(&str, &str)] = &[
+    ("'&'", "'&amp;'"),
+    ("'<'", "'&lt;'"),
+    ("'>'", "'&gt;'"),
+    ("'\"'", "'&quot;'"),
+    ("''''", "'&#39;'"),
+];
 
 #[derive(Iden)]
 pub enum CommonIden {
     Id,
+    OwnerId,
+    AssigneeId,
+    DeletedAt,
+    Version,
+}
+
+#[derive(Iden)]
+pub enum TimestampIden {
+    Cid,
+    Ctime,
+    Mid,
+    Mtime,
+}
+
+/// A page of a list, as returned by `list_page`.
+#[derive(Debug, Serialize)]
+pub struct ListPage<E> {
+    pub items: Vec<E>,
+    /// The number of entities matching the filter (only when requested).
+    pub total: Option<i64>,
+    pub limit: i64,
+    pub offset: i64,
+    /// `true` if there are entities after this page.
+    pub has_more: bool,
+    /// The cursor of the next page (when `has_more`), to pass in the `PageOptions` of the
+    /// next `list_page` call with the same `order_bys`.
+    pub next_cursor: Option<String>,
+}
+
+/// The options of a `list_page`, in addition to the `ListOptions`.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageOptions {
+    /// Also count the entities matching the filter (extra query).
+    #[serde(default)]
+    pub with_total: bool,
+    /// The `next_cursor` of the previous page. (Cannot be combined with an `offset`)
+    pub cursor: Option<String>,
+}
+
+/// An entity matching a `search`.
+#[derive(Debug, Serialize)]
+pub struct SearchHit<E> {
+    #[serde(flatten)]
+    pub entity: E,
+    /// The `ts_rank` of the entity for the search query.
+    pub rank: f32,
+    /// The fragments of the searchable text with the matching words highlighted
+    /// (`<b>...</b>`). The text is HTML escaped, so the `<b>` tags are its only markup.
+    pub snippet: String,
+}
+
+impl<'r, E> FromRow<'r, PgRow> for SearchHit<E>
+where
+    E: FromRow<'r, PgRow>,
+{
+    fn from_row(row: &'r PgRow) -> sqlx::Result<Self> {
+        Ok(SearchHit {
+            entity: E::from_row(row)?,
+            rank: row.try_get("rank")?,
+            snippet: row.try_get("snippet")?,
+        })
+    }
 }
 
 /// Database Backend Model Controller
@@ -29,6 +116,253 @@ pub trait DbBmc {
     fn table_ref() -> TableRef {
         TableRef::Table(SIden(Self::TABLE).into_iden())
     }
+
+    /// Returns `true` if the entity table has an `owner_id` column.
+    /// For those entities `create` sets the `owner_id` from the `Ctx`, while `get`, `list`,
+    /// `update` and `delete` only see the rows owned by the `Ctx` user.
+    /// (The root `Ctx` bypasses the ownership check.)
+    fn has_owner_id() -> bool {
+        false
+    }
+
+    /// Returns `true` if the entity table (with an `owner_id`) also has a nullable
+    /// `assignee_id` column. The assignee can read the row (`get`, `list`, `count`, `search`)
+    /// like the owner, but only the owner can modify it.
+    fn has_assignee_id() -> bool {
+        false
+    }
+
+    /// Returns `true` if the entity table has the `cid`, `ctime`, `mid` and `mtime` columns.
+    /// For those entities `create` and `update` fill them in from the `Ctx` and the current UTC time.
+    fn has_timestamps() -> bool {
+        false
+    }
+
+    /// Returns `true` if the entity table has a nullable `deleted_at` column.
+    /// For those entities `delete` only marks the row as deleted, `get`, `list` and `update`
+    /// ignore the marked rows, and `restore`, `list_deleted` and `purge` operate on them.
+    fn has_soft_delete() -> bool {
+        false
+    }
+
+    /// Returns `true` if the entity table has a `version` column (`BIGINT NOT NULL DEFAULT 0`).
+    /// For those entities every update increments the `version`, and `update_with_version`
+    /// can reject the update of a row modified since it was read.
+    fn has_version() -> bool {
+        false
+    }
+
+    /// Returns `true` if the changes of the entity are recorded in the `history` table.
+    /// For those entities the `update` and `delete` functions record the changed columns
+    /// (see `history`) in the same transaction as the change.
+    fn has_history() -> bool {
+        false
+    }
+
+    /// Returns `true` if the changes of the entity emit domain events (e.g., `TaskCreated`)
+    /// into the `outbox` table, in the same transaction as the change (see `outbox`).
+    fn has_events() -> bool {
+        false
+    }
+
+    /// Returns the columns of the natural unique key used by `upsert`
+    /// (e.g., `&["owner_id", "external_ref"]`), backed by a unique constraint on the table.
+    /// Empty if the entity does not support upserts.
+    fn upsert_key() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the SQL expression of the searchable text of the entity, if the entity table
+    /// has a `search_vector` column (`tsvector` generated from the same text).
+    /// Used by `search` for the highlighted snippets.
+    fn search_document() -> Option<&'static str> {
+        None
+    }
+}
+
+/// The result of an `upsert`.
+#[derive(Debug, Serialize)]
+pub struct Upserted {
+    pub id: i64,
+    /// `true` if the entity was created, `false` if it was updated.
+    pub inserted: bool,
+}
+
+/// Adds the creator / modifier ids and the UTC timestamps to the `fields` of a new entity.
+/// (`mid` and `mtime` start out equal to `cid` and `ctime`)
+pub fn add_timestamps_for_create(fields: &mut Fields, user_id: i64) {
+    let now = now_utc();
+    fields.push(Field::new(TimestampIden::Cid, user_id.into()));
+    fields.push(Field::new(TimestampIden::Ctime, now.into()));
+
+    fields.push(Field::new(TimestampIden::Mid, user_id.into()));
+    fields.push(Field::new(TimestampIden::Mtime, now.into()));
+}
+
+/// Adds the modifier id and the UTC timestamp to the `fields` of an updated entity.
+pub fn add_timestamps_for_update(fields: &mut Fields, user_id: i64) {
+    let now = now_utc();
+    fields.push(Field::new(TimestampIden::Mid, user_id.into()));
+    fields.push(Field::new(TimestampIden::Mtime, now.into()));
+}
+
+/// Adds the `version = version + 1` increment to the `fields` of an updated entity.
+fn add_version_bump(fields: &mut Fields) {
+    fields.push(Field::new(
+        CommonIden::Version,
+        Expr::col(CommonIden::Version).add(1),
+    ));
+}
+
+/// The kind of change of entities, recorded in their history and events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ChangeKind {
+    Create,
+    Update,
+    /// The soft delete, or the delete of an entity without soft delete.
+    Delete,
+    Restore,
+    /// The permanent delete of a soft deleted entity.
+    Purge,
+}
+
+/// Rows of an entity table as json objects (`to_jsonb`), with their ids.
+pub(super) type JsonRows = Vec<(i64, Value)>;
+
+/// Returns `true` if the changes of the entity are recorded (history or events).
+fn tracks_changes<MC>() -> bool
+where
+    MC: DbBmc,
+{
+    MC::has_history() || MC::has_events()
+}
+
+/// Returns the `ModelManager` of a write, transactional if the entity `tracks_changes()`
+/// (to commit with `mm.commit_txn_of`, once the change recorded).
+fn write_mm<MC>(mm: &ModelManager) -> ModelManager
+where
+    MC: DbBmc,
+{
+    if tracks_changes::<MC>() {
+        mm.new_with_txn()
+    } else {
+        mm.clone()
+    }
+}
+
+/// Returns the rows matching the `cond` before their change, locked until the end of the
+/// transaction of the `mm` (empty if the entity does not `tracks_changes()`).
+async fn snapshot<MC>(mm: &ModelManager, cond: Condition) -> Result<JsonRows>
+where
+    MC: DbBmc,
+{
+    if !tracks_changes::<MC>() {
+        return Ok(Vec::new());
+    }
+
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .column(CommonIden::Id)
+        .expr(Expr::cust(format!("to_jsonb(\"{}\")", MC::TABLE)))
+        .cond_where(cond)
+        .order_by(CommonIden::Id, Order::Asc)
+        .lock_exclusive();
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64, Value), _>(&sql, values);
+    let rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+    Ok(rows)
+}
+
+/// Records the change of the entities of the `ids` by the `Ctx` user, with their rows
+/// `before` the change (from `snapshot`, none for a `Create`), in their history and events.
+async fn record_change<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    kind: ChangeKind,
+    ids: &[i64],
+    before: JsonRows,
+) -> Result<()>
+where
+    MC: DbBmc,
+{
+    if !tracks_changes::<MC>() || ids.is_empty() {
+        return Ok(());
+    }
+
+    // The rows after the change (none for the deleted rows).
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .column(CommonIden::Id)
+        .expr(Expr::cust(format!("to_jsonb(\"{}\")", MC::TABLE)))
+        .and_where(Expr::col(CommonIden::Id).is_in(ids.iter().copied()));
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64, Value), _>(&sql, values);
+    let after = mm.dbx().fetch_all(sqlx_query).await?;
+
+    if MC::has_history() {
+        history::record::<MC>(ctx, mm, kind, &before, &after).await?;
+    }
+    if MC::has_events() {
+        outbox::record::<MC>(ctx, mm, kind, ids, &before, &after).await?;
+    }
+
+    Ok(())
+}
+
+/// The access of a query to the rows of the entities with an owner.
+#[derive(Clone, Copy)]
+enum Access {
+    /// Only the owner (the writes, and the reads of the soft deleted rows).
+    Owner,
+    /// The owner, or the assignee if the entity `has_assignee_id()` (the reads of the live rows).
+    Reader,
+}
+
+/// Returns the condition restricting a query to the rows the `Ctx` user has the `access` to,
+/// or `None` if the entity has no owner or the `Ctx` is the root `Ctx`.
+fn owner_cond<MC>(ctx: &Ctx, access: Access) -> Option<SimpleExpr>
+where
+    MC: DbBmc,
+{
+    if !MC::has_owner_id() || ctx.is_root() {
+        return None;
+    }
+
+    let owner_cond = Expr::col(CommonIden::OwnerId).eq(ctx.user_id());
+    match access {
+        Access::Reader if MC::has_assignee_id() => {
+            Some(owner_cond.or(Expr::col(CommonIden::AssigneeId).eq(ctx.user_id())))
+        }
+        _ => Some(owner_cond),
+    }
+}
+
+/// Returns the condition restricting a query to the rows the `Ctx` user can read (see
+/// `Access::Reader`), for the Bmc specific queries.
+pub fn reader_cond<MC>(ctx: &Ctx) -> Option<SimpleExpr>
+where
+    MC: DbBmc,
+{
+    owner_cond::<MC>(ctx, Access::Reader)
+}
+
+/// Returns the condition restricting a query to the soft deleted rows (`deleted == true`)
+/// or to the live ones (`deleted == false`), or `None` if the entity has no soft delete.
+fn deleted_cond<MC>(deleted: bool) -> Option<SimpleExpr>
+where
+    MC: DbBmc,
+{
+    if !MC::has_soft_delete() {
+        None
+    } else if deleted {
+        Some(Expr::col(CommonIden::DeletedAt).is_not_null())
+    } else {
+        Some(Expr::col(CommonIden::DeletedAt).is_null())
+    }
 }
 
 /// Checks that the provided `ListOptions` contains an acceptable `limit` value.
@@ -42,6 +376,12 @@ pub fn finalize_list_options(list_options: Option<ListOptions>) -> Result<ListOp
                     actual: limit,
                 });
             }
+            if limit < 1 {
+                return Err(Error::ListLimitUnderMin {
+                    min: 1,
+                    actual: limit,
+                });
+            }
         } else {
             list_options.limit = Some(LIST_LIMIT_DEFAULT);
         }
@@ -55,15 +395,40 @@ pub fn finalize_list_options(list_options: Option<ListOptions>) -> Result<ListOp
     }
 }
 
-pub async fn create<MC, E>(_ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
+/// Fails with `Error::ListOrderByInvalid` if one of the `order_bys` columns is not a field
+/// of the entity.
+fn check_order_bys<E>(list_options: &ListOptions) -> Result<()>
+where
+    E: HasFields,
+{
+    let order_bys = list_options.order_bys.iter().flatten();
+    for order_by in order_bys {
+        let column = match order_by {
+            OrderBy::Asc(column) | OrderBy::Desc(column) => column,
+        };
+        if !E::field_names().contains(&column.as_str()) {
+            return Err(Error::ListOrderByInvalid(column.to_string()));
+        }
+    }
+    Ok(())
+}
+
+pub async fn create<MC, E>(ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
 where
     MC: DbBmc,
     E: HasFields,
 {
-    let db = mm.db();
+    create_fields::<MC>(ctx, mm, data.not_none_fields()).await
+}
 
+/// Same as `create` but with the `fields` to insert, which can include the `owner_id`
+/// (e.g., for an entity created on behalf of another user).
+pub async fn create_fields<MC>(ctx: &Ctx, mm: &ModelManager, fields: Fields) -> Result<i64>
+where
+    MC: DbBmc,
+{
     // Extract fields (name / sea-query value expression)
-    let fields = data.not_none_fields();
+    let fields = add_fields_for_create::<MC>(ctx, fields);
     let (columns, sea_values) = fields.for_sea_insert();
 
     // Build Query
@@ -75,137 +440,1026 @@ where
         .returning(Query::returning().columns([CommonIden::Id]));
 
     // Exec Query
+    let mm_txn = write_mm::<MC>(mm);
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let (id,) = sqlx::query_as_with::<_, (i64,), _>(&sql, values)
-        .fetch_one(db)
-        .await?;
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let (id,) = mm_txn.dbx().fetch_one(sqlx_query).await?;
+    record_change::<MC>(ctx, &mm_txn, ChangeKind::Create, &[id], Vec::new()).await?;
+    mm.commit_txn_of(&mm_txn).await?;
 
     Ok(id)
 }
 
-pub async fn get<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+/// Creates the entities with a single multi-row `INSERT` and returns their ids,
+/// in the `data` order.
+/// The inserted columns are the union of the `data` fields, and an entity without one of
+/// them gets the column `DEFAULT`.
+pub async fn create_many<MC, E>(ctx: &Ctx, mm: &ModelManager, data: Vec<E>) -> Result<Vec<i64>>
 where
     MC: DbBmc,
-    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
     E: HasFields,
 {
-    let db = mm.db();
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    check_bulk_size(data.len())?;
 
-    let mut query = Query::select();
+    // Extract fields and the union of their columns
+    let rows: Vec<Vec<Field>> = data
+        .into_iter()
+        .map(|entity| fields_for_create::<MC, E>(ctx, entity).into_vec())
+        .collect();
+    let mut columns: Vec<DynIden> = Vec::new();
+    for field in rows.iter().flatten() {
+        let name = field.iden.to_string();
+        if !columns.iter().any(|column| column.to_string() == name) {
+            columns.push(field.iden.clone());
+        }
+    }
+
+    // Build Query
+    let mut query = Query::insert();
     query
-        .from(MC::table_ref())
-        .columns(E::field_column_refs())
-        .and_where(Expr::col(CommonIden::Id).eq(id));
+        .into_table(MC::table_ref())
+        .columns(columns.clone())
+        .returning(Query::returning().columns([CommonIden::Id]));
+    for fields in rows {
+        let mut values: HashMap<String, SimpleExpr> = fields
+            .into_iter()
+            .map(|field| (field.iden.to_string(), field.value))
+            .collect();
+        let row = columns
+            .iter()
+            .map(|column| {
+                values
+                    .remove(&column.to_string())
+                    .unwrap_or_else(|| Expr::cust("DEFAULT"))
+            })
+            .collect::<Vec<_>>();
+        query.values(row)?;
+    }
 
+    // Exec Query
+    // NOTE: Postgres returns the `RETURNING` rows of a multi-row `VALUES` in its order.
+    let mm_txn = write_mm::<MC>(mm);
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entity = sqlx::query_as_with::<_, E, _>(&sql, values)
-        .fetch_optional(db)
-        .await?
-        .ok_or(Error::EntityNotFound {
-            entity: MC::TABLE,
-            id,
-        })?;
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let ids = mm_txn.dbx().fetch_all(sqlx_query).await?;
+    let ids = ids.into_iter().map(|(id,)| id).collect::<Vec<_>>();
+    record_change::<MC>(ctx, &mm_txn, ChangeKind::Create, &ids, Vec::new()).await?;
+    mm.commit_txn_of(&mm_txn).await?;
 
-    Ok(entity)
+    Ok(ids)
 }
 
-pub async fn list<MC, E, F>(
-    _ctx: &Ctx,
-    mm: &ModelManager,
-    filter: Option<F>,
-    list_options: Option<ListOptions>,
-) -> Result<Vec<E>>
+/// Creates the entity, or updates the existing one with the same `upsert_key()` values,
+/// with a single `INSERT ... ON CONFLICT (...) DO UPDATE`.
+///
+/// - On update, all the `data` fields but the key are set, the `cid` and `ctime` are kept,
+///   and the `version` is incremented.
+/// - Upserting a soft deleted entity restores it.
+/// - The key includes the `owner_id` for the owned entities, so a `Ctx` user only ever
+///   updates its own entities.
+pub async fn upsert<MC, E>(ctx: &Ctx, mm: &ModelManager, data: E) -> Result<Upserted>
 where
     MC: DbBmc,
-    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
     E: HasFields,
-    F: Into<FilterGroups>,
 {
-    let db = mm.db();
+    let key = MC::upsert_key();
+    if key.is_empty() {
+        return Err(Error::UpsertKeyMissing { entity: MC::TABLE });
+    }
 
-    // Build
-    let mut query = Query::select();
-    query.from(MC::table_ref()).columns(E::field_column_refs());
+    // Extract fields
+    let fields = fields_for_create::<MC, E>(ctx, data);
+    let (columns, sea_values) = fields.for_sea_insert();
 
-    // Condition from Filter
-    if let Some(filter) = filter {
-        let filters: FilterGroups = filter.into();
-        let cond: Condition = filters.try_into()?;
-        query.cond_where(cond);
+    // The condition matching the existing entity (if any), when all the key columns are set.
+    let key_exprs = columns
+        .iter()
+        .zip(sea_values.iter())
+        .filter(|(column, _)| key.contains(&column.to_string().as_str()))
+        .map(|(column, value)| Expr::col(column.clone()).eq(value.clone()))
+        .collect::<Vec<_>>();
+    let key_cond = (key_exprs.len() == key.len())
+        .then(|| key_exprs.into_iter().fold(Condition::all(), Condition::add));
+
+    // Columns updated on conflict
+    let kept = ["owner_id", "cid", "ctime"];
+    let update_columns = columns
+        .iter()
+        .filter(|column| {
+            let name = column.to_string();
+            !key.contains(&name.as_str()) && !kept.contains(&name.as_str())
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut on_conflict = OnConflict::columns(key.iter().copied().map(SIden));
+    on_conflict.update_columns(update_columns);
+    if MC::has_version() {
+        on_conflict.value(
+            CommonIden::Version,
+            Expr::col((SIden(MC::TABLE), CommonIden::Version)).add(1),
+        );
+    }
+    if MC::has_soft_delete() {
+        on_conflict.value(CommonIden::DeletedAt, Expr::cust("NULL"));
     }
 
-    // List Options
-    let list_opt = finalize_list_options(list_options)?;
-    list_opt.apply_to_sea_query(&mut query);
+    // Build Query
+    // NOTE: `xmax` is 0 for a row inserted by the statement, and set for an updated one.
+    let mut query = Query::insert();
+    query
+        .into_table(MC::table_ref())
+        .columns(columns)
+        .values(sea_values)?
+        .on_conflict(on_conflict)
+        .returning(
+            Query::returning().exprs([Expr::col(CommonIden::Id).into(), Expr::cust("xmax = 0")]),
+        );
 
-    // Exec
+    // Exec Query
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entities = sqlx::query_as_with::<_, E, _>(&sql, values)
-        .fetch_all(db)
-        .await?;
+    let mm_txn = write_mm::<MC>(mm);
+    let before = match key_cond {
+        Some(key_cond) => snapshot::<MC>(&mm_txn, key_cond).await?,
+        None => Vec::new(),
+    };
+    let sqlx_query = sqlx::query_as_with::<_, (i64, bool), _>(&sql, values);
+    let (id, inserted) = mm_txn.dbx().fetch_one(sqlx_query).await?;
+    let (kind, before) = if inserted {
+        (ChangeKind::Create, Vec::new())
+    } else {
+        (ChangeKind::Update, before)
+    };
+    record_change::<MC>(ctx, &mm_txn, kind, &[id], before).await?;
+    mm.commit_txn_of(&mm_txn).await?;
 
-    Ok(entities)
+    Ok(Upserted { id, inserted })
 }
 
-pub async fn update<MC, E>(_ctx: &Ctx, mm: &ModelManager, id: i64, data: E) -> Result<()>
+/// Returns the fields of a new entity, with its `owner_id` and timestamps when the entity
+/// has them.
+fn fields_for_create<MC, E>(ctx: &Ctx, data: E) -> Fields
 where
     MC: DbBmc,
     E: HasFields,
 {
-    let db = mm.db();
-
-    // Prep data
-    let fields = data.not_none_fields();
-    let fields = fields.for_sea_update();
-
-    // Build Query
-    let mut query = Query::update();
-    query
-        .table(MC::table_ref())
-        .values(fields)
-        .and_where(Expr::col(CommonIden::Id).eq(id));
+    add_fields_for_create::<MC>(ctx, data.not_none_fields())
+}
 
-    // Exec Query
-    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
-        .await?
-        .rows_affected();
+/// Adds the `owner_id` (the `Ctx` user, unless already in the `fields`) and the timestamps
+/// of a new entity to the `fields`, when the entity has them.
+fn add_fields_for_create<MC>(ctx: &Ctx, mut fields: Fields) -> Fields
+where
+    MC: DbBmc,
+{
+    if MC::has_owner_id() {
+        let owner_id = CommonIden::OwnerId.to_string();
+        let has_owner_id = fields
+            .clone()
+            .into_iter()
+            .any(|field| field.iden.to_string() == owner_id);
+        if !has_owner_id {
+            fields.push(Field::new(CommonIden::OwnerId, ctx.user_id().into()));
+        }
+    }
+    if MC::has_timestamps() {
+        add_timestamps_for_create(&mut fields, ctx.user_id());
+    }
+    fields
+}
 
-    // Check result
-    if count == 0 {
-        Err(Error::EntityNotFound {
-            entity: MC::TABLE,
-            id,
-        })
-    } else {
-        Ok(())
+fn check_bulk_size(actual: usize) -> Result<()> {
+    if actual > BULK_MAX {
+        return Err(Error::BulkOverMax {
+            max: BULK_MAX,
+            actual,
+        });
     }
+    Ok(())
+}
+
+pub async fn get<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    get_by_state::<MC, E>(ctx, mm, id, false).await
 }
 
-pub async fn delete<MC>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+/// Same as `get` but only returns a soft deleted entity.
+pub async fn get_deleted<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
 where
     MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
 {
-    let db = mm.db();
+    get_by_state::<MC, E>(ctx, mm, id, true).await
+}
 
-    let mut query = Query::delete();
+async fn get_by_state<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64, deleted: bool) -> Result<E>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    let mut query = Query::select();
     query
-        .from_table(MC::table_ref())
+        .from(MC::table_ref())
+        .columns(E::field_column_refs())
         .and_where(Expr::col(CommonIden::Id).eq(id));
+    let access = if deleted {
+        Access::Owner
+    } else {
+        Access::Reader
+    };
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, access) {
+        query.and_where(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(deleted) {
+        query.and_where(deleted_cond);
+    }
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
+    let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+    let entity = mm
+        .dbx()
+        .fetch_optional(sqlx_query)
         .await?
-        .rows_affected();
-
-    if count == 0 {
-        Err(Error::EntityNotFound {
+        .ok_or(Error::EntityNotFound {
             entity: MC::TABLE,
             id,
-        })
-    } else {
-        Ok(())
-    }
+        })?;
+
+    Ok(entity)
+}
+
+pub async fn list<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+) -> Result<Vec<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let cond = list_cond::<MC, F>(ctx, filter, false, Access::Reader)?;
+    let list_options = finalize_list_options(list_options)?;
+    list_by_cond::<MC, E>(mm, cond, list_options).await
+}
+
+/// Same as `list` but only returns the soft deleted entities (i.e., the trash).
+pub async fn list_deleted<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+) -> Result<Vec<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let cond = list_cond::<MC, F>(ctx, filter, true, Access::Owner)?;
+    let list_options = finalize_list_options(list_options)?;
+    list_by_cond::<MC, E>(mm, cond, list_options).await
+}
+
+/// Same as `list` but returns the page envelope, with the `total` count of the entities
+/// matching the `filter` if `page_options.with_total` is `true`, and the `next_cursor`
+/// to list the following page from (keyset pagination).
+pub async fn list_page<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+    page_options: PageOptions,
+) -> Result<ListPage<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let cond = list_cond::<MC, F>(ctx, filter, false, Access::Reader)?;
+    list_page_by_cond::<MC, E>(mm, cond, list_options, page_options).await
+}
+
+/// Same as `list_page` but only for the soft deleted entities.
+pub async fn list_deleted_page<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+    page_options: PageOptions,
+) -> Result<ListPage<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let cond = list_cond::<MC, F>(ctx, filter, true, Access::Owner)?;
+    list_page_by_cond::<MC, E>(mm, cond, list_options, page_options).await
+}
+
+/// Returns the number of entities `list` would return for the `filter` without a limit.
+pub async fn count<MC, F>(ctx: &Ctx, mm: &ModelManager, filter: Option<F>) -> Result<i64>
+where
+    MC: DbBmc,
+    F: Into<FilterGroups>,
+{
+    let cond = list_cond::<MC, F>(ctx, filter, false, Access::Reader)?;
+    count_by_cond::<MC>(mm, cond).await
+}
+
+/// Returns the entities matching the full-text `query` (web search syntax, e.g.,
+/// `"fix login" -mobile`) and the `filter`, from the most to the least relevant.
+/// Only the `limit` and `offset` of the `list_options` apply.
+/// (Only for the entities with a `search_document()`)
+pub async fn search<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    query: &str,
+    filter: Option<F>,
+    list_options: Option<ListOptions>,
+) -> Result<Vec<SearchHit<E>>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let document = MC::search_document().ok_or(Error::SearchNotSupported { entity: MC::TABLE })?;
+    let list_options = finalize_list_options(list_options)?;
+
+    let ts_query = || {
+        Expr::cust_with_values(
+            format!("websearch_to_tsquery('{SEARCH_CONFIG}', $1)"),
+            [query.to_string()],
+        )
+    };
+    let rank = Expr::cust_with_expr("ts_rank(search_vector, $1)", ts_query());
+    // NOTE: The document is HTML escaped before adding the highlight tags, as the
+    //       user text could otherwise inject markup in the snippet.
+    let escaped_document = SEARCH_SNIPPET_ESCAPES
+        .iter()
+        .fold(format!("({document})"), |expr, (from, to)| {
+            format!("replace({expr}, {from}, {to})")
+        });
+    let snippet = Expr::cust_with_exprs(
+        format!(
+            "ts_headline('{SEARCH_CONFIG}', {escaped_document}, $1, '{SEARCH_SNIPPET_OPTIONS}')"
+        ),
+        [ts_query()],
+    );
+    let cond = list_cond::<MC, F>(ctx, filter, false, Access::Reader)?
+        .add(Expr::cust_with_expr("search_vector @@ $1", ts_query()));
+
+    // Build
+    let mut sql_query = Query::select();
+    sql_query
+        .from(MC::table_ref())
+        .columns(E::field_column_refs())
+        .expr_as(rank, Alias::new("rank"))
+        .expr_as(snippet, Alias::new("snippet"))
+        .cond_where(cond);
+    ListOptions {
+        order_bys: None,
+        ..list_options
+    }
+    .apply_to_sea_query(&mut sql_query);
+    sql_query
+        .order_by_expr(Expr::cust("rank"), Order::Desc)
+        .order_by(CommonIden::Id, Order::Asc);
+
+    // Exec
+    let (sql, values) = sql_query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, SearchHit<E>, _>(&sql, values);
+    let hits = mm.dbx().fetch_all(sqlx_query).await?;
+
+    Ok(hits)
+}
+
+/// Returns the live entities of the `ids` visible to the `Ctx`, ordered by id.
+/// (The `ids` not found are skipped)
+pub async fn list_by_ids<MC, E>(ctx: &Ctx, mm: &ModelManager, ids: &[i64]) -> Result<Vec<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    let cond = ids_cond::<MC>(ctx, ids, Access::Reader);
+    let list_options = ListOptions {
+        limit: None,
+        offset: None,
+        order_bys: Some("id".into()),
+    };
+    list_by_cond::<MC, E>(mm, cond, list_options).await
+}
+
+/// Returns the condition of the `list` and `count` queries, made of the `filter`,
+/// ownership (for the `access`) and soft delete conditions.
+fn list_cond<MC, F>(
+    ctx: &Ctx,
+    filter: Option<F>,
+    deleted: bool,
+    access: Access,
+) -> Result<Condition>
+where
+    MC: DbBmc,
+    F: Into<FilterGroups>,
+{
+    let mut cond = Condition::all();
+
+    // Condition from Filter
+    if let Some(filter) = filter {
+        let filters: FilterGroups = filter.into();
+        let filter_cond: Condition = filters.try_into()?;
+        cond = cond.add(filter_cond);
+    }
+
+    // Condition from Ownership and soft delete
+    // NOTE: `cond_where` and `and_where` cannot be mixed in the same sea-query statement,
+    //       so those are added to the same condition.
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, access) {
+        cond = cond.add(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(deleted) {
+        cond = cond.add(deleted_cond);
+    }
+
+    Ok(cond)
+}
+
+/// Returns the condition of the bulk writes by `filter` on the live (or `deleted`) entities
+/// (see `list_cond`).
+/// Fails with `Error::BulkFilterEmpty` if the `filter` (or one of its groups) is empty, as it
+/// would match all the entities of the `Ctx`.
+fn bulk_filter_cond<MC, F>(ctx: &Ctx, filter: F, deleted: bool) -> Result<Condition>
+where
+    MC: DbBmc,
+    F: Into<FilterGroups>,
+{
+    let filters: FilterGroups = filter.into();
+    let groups = filters.groups();
+    if groups.is_empty() || groups.iter().any(|group| group.nodes().is_empty()) {
+        return Err(Error::BulkFilterEmpty);
+    }
+
+    list_cond::<MC, FilterGroups>(ctx, Some(filters), deleted, Access::Owner)
+}
+
+/// Returns the condition matching the live entities of the `ids` the `Ctx` has the `access` to.
+fn ids_cond<MC>(ctx: &Ctx, ids: &[i64], access: Access) -> Condition
+where
+    MC: DbBmc,
+{
+    let mut cond = Condition::all().add(Expr::col(CommonIden::Id).is_in(ids.iter().copied()));
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, access) {
+        cond = cond.add(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(false) {
+        cond = cond.add(deleted_cond);
+    }
+    cond
+}
+
+async fn list_by_cond<MC, E>(
+    mm: &ModelManager,
+    cond: Condition,
+    list_options: ListOptions,
+) -> Result<Vec<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    check_order_bys::<E>(&list_options)?;
+
+    // Build
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .columns(E::field_column_refs())
+        .cond_where(cond);
+
+    // List Options
+    list_options.apply_to_sea_query(&mut query);
+
+    // Exec
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+    let entities = mm.dbx().fetch_all(sqlx_query).await?;
+
+    Ok(entities)
+}
+
+async fn list_page_by_cond<MC, E>(
+    mm: &ModelManager,
+    mut cond: Condition,
+    list_options: Option<ListOptions>,
+    page_options: PageOptions,
+) -> Result<ListPage<E>>
+where
+    MC: DbBmc,
+    E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+    E: HasFields,
+{
+    let mut list_options = finalize_list_options(list_options)?;
+    check_order_bys::<E>(&list_options)?;
+    let limit = list_options.limit.unwrap_or(LIST_LIMIT_DEFAULT);
+    let offset = list_options.offset.unwrap_or(0);
+
+    // The sort key (with the `id` tiebreaker) gives a stable order for the cursors.
+    let sort_key = SortKey::from_order_bys(list_options.order_bys.take().as_ref())?;
+
+    let total = if page_options.with_total {
+        Some(count_by_cond::<MC>(mm, cond.clone()).await?)
+    } else {
+        None
+    };
+
+    if let Some(cursor) = page_options.cursor {
+        if list_options.offset.is_some() {
+            return Err(Error::ListCursorWithOffset);
+        }
+        let key = sort_key.decode_cursor(&cursor)?;
+        cond = cond.add(sort_key.after_cond(MC::TABLE, &key)?);
+    }
+
+    // Build
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .columns(E::field_column_refs())
+        .expr_as(sort_key.key_expr(), Alias::new(KEY_COLUMN))
+        .cond_where(cond);
+
+    // Fetch one more row than the limit to know if there are more.
+    list_options.limit = Some(limit + 1);
+    list_options.apply_to_sea_query(&mut query);
+    sort_key.apply_order(&mut query);
+
+    // Exec
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, Keyed<E>, _>(&sql, values);
+    let mut rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = match rows.last() {
+        Some(last) if has_more => Some(sort_key.encode_cursor(&last.key)?),
+        _ => None,
+    };
+    let items = rows.into_iter().map(|row| row.entity).collect();
+
+    Ok(ListPage {
+        items,
+        total,
+        limit,
+        offset,
+        has_more,
+        next_cursor,
+    })
+}
+
+async fn count_by_cond<MC>(mm: &ModelManager, cond: Condition) -> Result<i64>
+where
+    MC: DbBmc,
+{
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .expr(Expr::col(Asterisk).count())
+        .cond_where(cond);
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let (count,) = mm.dbx().fetch_one(sqlx_query).await?;
+
+    Ok(count)
+}
+
+pub async fn update<MC, E>(ctx: &Ctx, mm: &ModelManager, id: i64, data: E) -> Result<()>
+where
+    MC: DbBmc,
+    E: HasFields,
+{
+    update_with_version::<MC, E>(ctx, mm, id, None, data).await
+}
+
+/// Updates the entity only if its current `version` is the expected `version`
+/// (when provided and the entity `has_version()`).
+/// Fails with `Error::VersionConflict` if the entity exists but has been modified since.
+pub async fn update_with_version<MC, E>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    id: i64,
+    version: Option<i64>,
+    data: E,
+) -> Result<()>
+where
+    MC: DbBmc,
+    E: HasFields,
+{
+    update_fields_with_version::<MC>(ctx, mm, id, version, data.not_none_fields()).await
+}
+
+/// Same as `update` but with the `fields` to set, including the `None` (`NULL`) ones.
+pub async fn update_fields<MC>(ctx: &Ctx, mm: &ModelManager, id: i64, fields: Fields) -> Result<()>
+where
+    MC: DbBmc,
+{
+    update_fields_with_version::<MC>(ctx, mm, id, None, fields).await
+}
+
+async fn update_fields_with_version<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    id: i64,
+    version: Option<i64>,
+    mut fields: Fields,
+) -> Result<()>
+where
+    MC: DbBmc,
+{
+    let version = version.filter(|_| MC::has_version());
+
+    // Prep data
+    if MC::has_timestamps() {
+        add_timestamps_for_update(&mut fields, ctx.user_id());
+    }
+    if MC::has_version() {
+        add_version_bump(&mut fields);
+    }
+    let fields = fields.for_sea_update();
+
+    // Build Query
+    let mut cond = Condition::all().add(Expr::col(CommonIden::Id).eq(id));
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, Access::Owner) {
+        cond = cond.add(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(false) {
+        cond = cond.add(deleted_cond);
+    }
+    if let Some(version) = version {
+        cond = cond.add(Expr::col(CommonIden::Version).eq(version));
+    }
+    let mut query = Query::update();
+    query
+        .table(MC::table_ref())
+        .values(fields)
+        .cond_where(cond.clone());
+
+    // Exec Query
+    let mm_txn = write_mm::<MC>(mm);
+    let before = snapshot::<MC>(&mm_txn, cond).await?;
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    let count = mm_txn.dbx().execute(sqlx_query).await?;
+
+    // Check result
+    if count > 0 {
+        record_change::<MC>(ctx, &mm_txn, ChangeKind::Update, &[id], before).await?;
+        return mm.commit_txn_of(&mm_txn).await;
+    }
+    match version {
+        Some(expected) if exists::<MC>(ctx, &mm_txn, id).await? => Err(Error::VersionConflict {
+            entity: MC::TABLE,
+            id,
+            expected,
+        }),
+        _ => Err(Error::EntityNotFound {
+            entity: MC::TABLE,
+            id,
+        }),
+    }
+}
+
+/// Returns `true` if the live entity exists and is visible to the `Ctx`.
+async fn exists<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<bool>
+where
+    MC: DbBmc,
+{
+    let mut query = Query::select();
+    query
+        .from(MC::table_ref())
+        .column(CommonIden::Id)
+        .and_where(Expr::col(CommonIden::Id).eq(id));
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, Access::Owner) {
+        query.and_where(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(false) {
+        query.and_where(deleted_cond);
+    }
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let row = mm.dbx().fetch_optional(sqlx_query).await?;
+
+    Ok(row.is_some())
+}
+
+/// Applies the same `data` to all the entities of the `ids`, and returns the updated ids.
+/// Fails with `Error::EntityNotFound` (on the first missing id) if one of the entities does
+/// not exist, in which case the caller transaction should be rolled back.
+pub async fn update_many<MC, E>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    ids: &[i64],
+    data: E,
+) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+    E: HasFields,
+{
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    check_bulk_size(ids.len())?;
+
+    let cond = ids_cond::<MC>(ctx, ids, Access::Owner);
+    let fields = data.not_none_fields();
+    let updated_ids =
+        update_fields_by_cond::<MC>(ctx, mm, cond, fields, ChangeKind::Update).await?;
+
+    if let Some(id) = ids.iter().find(|id| !updated_ids.contains(id)) {
+        return Err(Error::EntityNotFound {
+            entity: MC::TABLE,
+            id: *id,
+        });
+    }
+
+    Ok(updated_ids)
+}
+
+/// Applies the same `data` to all the entities matching the `filter`, and returns the
+/// updated ids.
+pub async fn update_many_by_filter<MC, E, F>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    filter: F,
+    data: E,
+) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+    E: HasFields,
+    F: Into<FilterGroups>,
+{
+    let cond = bulk_filter_cond::<MC, F>(ctx, filter, false)?;
+    update_fields_by_cond::<MC>(ctx, mm, cond, data.not_none_fields(), ChangeKind::Update).await
+}
+
+/// Updates the `fields` of the live entities of the `ids`, whoever owns them, and returns
+/// their ids. (Recorded as an `Update` change)
+/// NOTE: For the Bmc follow-up updates of a change already access checked (e.g., the subtasks
+///       reparented on a task delete).
+pub async fn update_fields_of_ids<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    ids: &[i64],
+    fields: Fields,
+) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+{
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut cond = Condition::all().add(Expr::col(CommonIden::Id).is_in(ids.iter().copied()));
+    if let Some(deleted_cond) = deleted_cond::<MC>(false) {
+        cond = cond.add(deleted_cond);
+    }
+    update_fields_by_cond::<MC>(ctx, mm, cond, fields, ChangeKind::Update).await
+}
+
+/// Updates the `fields` (plus the timestamps and version) of the rows matching the `cond`,
+/// and returns their ids. (Recorded as a `kind` change)
+async fn update_fields_by_cond<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    cond: Condition,
+    mut fields: Fields,
+    kind: ChangeKind,
+) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+{
+    if MC::has_timestamps() {
+        add_timestamps_for_update(&mut fields, ctx.user_id());
+    }
+    if MC::has_version() {
+        add_version_bump(&mut fields);
+    }
+
+    let mut query = Query::update();
+    query
+        .table(MC::table_ref())
+        .values(fields.for_sea_update())
+        .cond_where(cond.clone())
+        .returning(Query::returning().columns([CommonIden::Id]));
+
+    let mm_txn = write_mm::<MC>(mm);
+    let before = snapshot::<MC>(&mm_txn, cond).await?;
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let ids = mm_txn.dbx().fetch_all(sqlx_query).await?;
+    let ids = ids.into_iter().map(|(id,)| id).collect::<Vec<_>>();
+    record_change::<MC>(ctx, &mm_txn, kind, &ids, before).await?;
+    mm.commit_txn_of(&mm_txn).await?;
+
+    Ok(ids)
+}
+
+/// Deletes the entity, or only marks it as deleted if the entity `has_soft_delete()`.
+pub async fn delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: DbBmc,
+{
+    if MC::has_soft_delete() {
+        set_deleted_at::<MC>(ctx, mm, id, Some(now_utc())).await
+    } else {
+        hard_delete::<MC>(ctx, mm, id).await
+    }
+}
+
+/// Restores a soft deleted entity.
+/// (Only for the entities that `has_soft_delete()`)
+pub async fn restore<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: DbBmc,
+{
+    set_deleted_at::<MC>(ctx, mm, id, None).await
+}
+
+/// Permanently deletes a soft deleted entity.
+/// For entities without soft delete it is the same as `delete`.
+pub async fn purge<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: DbBmc,
+{
+    hard_delete::<MC>(ctx, mm, id).await
+}
+
+/// Deletes (or marks as deleted, see `delete`) all the entities matching the `filter`,
+/// and returns their ids.
+pub async fn delete_many<MC, F>(ctx: &Ctx, mm: &ModelManager, filter: F) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+    F: Into<FilterGroups>,
+{
+    let cond = bulk_filter_cond::<MC, F>(ctx, filter, false)?;
+
+    if MC::has_soft_delete() {
+        let fields = Fields::new(vec![Field::new(
+            CommonIden::DeletedAt,
+            Some(now_utc()).into(),
+        )]);
+        return update_fields_by_cond::<MC>(ctx, mm, cond, fields, ChangeKind::Delete).await;
+    }
+
+    hard_delete_by_cond::<MC>(ctx, mm, cond, ChangeKind::Delete).await
+}
+
+/// Permanently deletes all the soft deleted entities matching the `filter`, and returns
+/// their ids. (Only for the entities that `has_soft_delete()`)
+pub async fn purge_many<MC, F>(ctx: &Ctx, mm: &ModelManager, filter: F) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+    F: Into<FilterGroups>,
+{
+    let cond = bulk_filter_cond::<MC, F>(ctx, filter, true)?;
+    hard_delete_by_cond::<MC>(ctx, mm, cond, ChangeKind::Purge).await
+}
+
+/// Deletes the rows matching the `cond`, and returns their ids. (Recorded as a `kind` change)
+async fn hard_delete_by_cond<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    cond: Condition,
+    kind: ChangeKind,
+) -> Result<Vec<i64>>
+where
+    MC: DbBmc,
+{
+    let mut query = Query::delete();
+    query
+        .from_table(MC::table_ref())
+        .cond_where(cond.clone())
+        .returning(Query::returning().columns([CommonIden::Id]));
+
+    let mm_txn = write_mm::<MC>(mm);
+    let before = snapshot::<MC>(&mm_txn, cond).await?;
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+    let ids = mm_txn.dbx().fetch_all(sqlx_query).await?;
+    let ids = ids.into_iter().map(|(id,)| id).collect::<Vec<_>>();
+    record_change::<MC>(ctx, &mm_txn, kind, &ids, before).await?;
+    mm.commit_txn_of(&mm_txn).await?;
+
+    Ok(ids)
+}
+
+/// Marks (`Some`) or unmarks (`None`) the entity as deleted.
+/// Only matches live entities when marking and deleted ones when unmarking.
+async fn set_deleted_at<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    id: i64,
+    deleted_at: Option<OffsetDateTime>,
+) -> Result<()>
+where
+    MC: DbBmc,
+{
+    // Prep data
+    let is_delete = deleted_at.is_some();
+    let mut fields = Fields::new(vec![Field::new(CommonIden::DeletedAt, deleted_at.into())]);
+    if MC::has_timestamps() {
+        add_timestamps_for_update(&mut fields, ctx.user_id());
+    }
+    if MC::has_version() {
+        add_version_bump(&mut fields);
+    }
+
+    // Build Query
+    let mut cond = Condition::all().add(Expr::col(CommonIden::Id).eq(id));
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, Access::Owner) {
+        cond = cond.add(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(!is_delete) {
+        cond = cond.add(deleted_cond);
+    }
+    let mut query = Query::update();
+    query
+        .table(MC::table_ref())
+        .values(fields.for_sea_update())
+        .cond_where(cond.clone());
+
+    // Exec Query
+    let mm_txn = write_mm::<MC>(mm);
+    let before = snapshot::<MC>(&mm_txn, cond).await?;
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    let count = mm_txn.dbx().execute(sqlx_query).await?;
+
+    if count == 0 {
+        return Err(Error::EntityNotFound {
+            entity: MC::TABLE,
+            id,
+        });
+    }
+    let kind = if is_delete {
+        ChangeKind::Delete
+    } else {
+        ChangeKind::Restore
+    };
+    record_change::<MC>(ctx, &mm_txn, kind, &[id], before).await?;
+    mm.commit_txn_of(&mm_txn).await
+}
+
+/// Deletes the row. For entities with soft delete, only a row already marked as deleted can be
+/// deleted.
+async fn hard_delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+    MC: DbBmc,
+{
+    let mut cond = Condition::all().add(Expr::col(CommonIden::Id).eq(id));
+    if let Some(owner_cond) = owner_cond::<MC>(ctx, Access::Owner) {
+        cond = cond.add(owner_cond);
+    }
+    if let Some(deleted_cond) = deleted_cond::<MC>(true) {
+        cond = cond.add(deleted_cond);
+    }
+    let mut query = Query::delete();
+    query.from_table(MC::table_ref()).cond_where(cond.clone());
+
+    let mm_txn = write_mm::<MC>(mm);
+    let before = snapshot::<MC>(&mm_txn, cond).await?;
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    let count = mm_txn.dbx().execute(sqlx_query).await?;
+
+    if count == 0 {
+        return Err(Error::EntityNotFound {
+            entity: MC::TABLE,
+            id,
+        });
+    }
+    let kind = if MC::has_soft_delete() {
+        ChangeKind::Purge
+    } else {
+        ChangeKind::Delete
+    };
+    record_change::<MC>(ctx, &mm_txn, kind, &[id], before).await?;
+    mm.commit_txn_of(&mm_txn).await
 }
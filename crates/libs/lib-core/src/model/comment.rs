@@ -0,0 +1,255 @@
+use modql::field::Fields;
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::{Error, Result};
+
+use super::base::{self, DbBmc};
+use super::modql_utils::time_to_sea_value;
+use super::task::TaskBmc;
+use super::ModelManager;
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Fields, Serialize)]
+pub struct Comment {
+    pub id: i64,
+    pub task_id: i64,
+    pub author_id: i64,
+    pub content: String,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+    pub mid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize)]
+pub struct CommentForCreate {
+    pub task_id: i64,
+    pub content: String,
+}
+
+/// The `CommentForCreate` with the `author_id` from the `Ctx`.
+#[derive(Fields)]
+struct CommentForInsert {
+    task_id: i64,
+    author_id: i64,
+    content: String,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct CommentForUpdate {
+    pub content: Option<String>,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct CommentFilter {
+    id: Option<OpValsInt64>,
+    task_id: Option<OpValsInt64>,
+    author_id: Option<OpValsInt64>,
+    content: Option<OpValsString>,
+
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Comment Backend Model Controller
+///
+/// NOTE: Comments have no owner of their own. They are visible to the users who can see their
+/// task, and only their author can update or delete them.
+pub struct CommentBmc;
+
+impl DbBmc for CommentBmc {
+    const TABLE: &'static str = "comment";
+
+    fn has_timestamps() -> bool {
+        true
+    }
+}
+
+impl CommentBmc {
+    /// Adds the comment to the task, with the `Ctx` user as author.
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, comment_c: CommentForCreate) -> Result<i64> {
+        // Make sure the task exists and is visible to the `Ctx` user.
+        TaskBmc::get(ctx, mm, comment_c.task_id).await?;
+
+        let comment_i = CommentForInsert {
+            task_id: comment_c.task_id,
+            author_id: ctx.user_id(),
+            content: comment_c.content,
+        };
+        base::create::<Self, _>(ctx, mm, comment_i).await
+    }
+
+    pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Comment> {
+        let comment: Comment = base::get::<Self, _>(ctx, mm, id).await?;
+
+        // Make sure the task of the comment is visible to the `Ctx` user.
+        TaskBmc::get(ctx, mm, comment.task_id)
+            .await
+            .map_err(|_| Error::EntityNotFound {
+                entity: Self::TABLE,
+                id,
+            })?;
+
+        Ok(comment)
+    }
+
+    /// Returns the comments of the task matching the `filters` (oldest first by default).
+    pub async fn list_for_task(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        task_id: i64,
+        filters: Option<Vec<CommentFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Comment>> {
+        // Make sure the task exists and is visible to the `Ctx` user.
+        TaskBmc::get(ctx, mm, task_id).await?;
+
+        // Restrict every filter group to the task.
+        let filters = filters
+            .filter(|filters| !filters.is_empty())
+            .unwrap_or_else(|| vec![CommentFilter::default()])
+            .into_iter()
+            .map(|filter| CommentFilter {
+                task_id: Some(task_id.into()),
+                ..filter
+            })
+            .collect::<Vec<_>>();
+
+        base::list::<Self, _, _>(ctx, mm, Some(filters), list_options).await
+    }
+
+    /// Updates the comment (only by its author).
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        data: CommentForUpdate,
+    ) -> Result<()> {
+        Self::check_author(ctx, mm, id).await?;
+        base::update::<Self, _>(ctx, mm, id, data).await
+    }
+
+    /// Deletes the comment (only by its author).
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        Self::check_author(ctx, mm, id).await?;
+        base::delete::<Self>(ctx, mm, id).await
+    }
+
+    /// Fails with `Error::AccessDenied` if the `Ctx` user is not the author of the visible
+    /// comment. (The root `Ctx` bypasses the check.)
+    async fn check_author(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let comment = Self::get(ctx, mm, id).await?;
+        if !ctx.is_root() && comment.author_id != ctx.user_id() {
+            return Err(Error::AccessDenied {
+                entity: Self::TABLE,
+                id,
+            });
+        }
+        Ok(())
+    }
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{self, seed_project, seed_tasks};
+    use crate::model::project::ProjectBmc;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_list_update_delete_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let fx_project_id =
+            seed_project(&ctx, &mm, "test_create_list_update_delete_ok project").await?;
+        let fx_task = seed_tasks(&ctx, &mm, fx_project_id, &["task 01"])
+            .await?
+            .remove(0);
+
+        // -- Exec
+        let mut ids = Vec::new();
+        for content in ["comment 01", "comment 02"] {
+            let comment_c = CommentForCreate {
+                task_id: fx_task.id,
+                content: content.to_string(),
+            };
+            ids.push(CommentBmc::create(&ctx, &mm, comment_c).await?);
+        }
+        let comment_u = CommentForUpdate {
+            content: Some("comment 01 edited".to_string()),
+        };
+        CommentBmc::update(&ctx, &mm, ids[0], comment_u).await?;
+        CommentBmc::delete(&ctx, &mm, ids[1]).await?;
+
+        // -- Check
+        let comments = CommentBmc::list_for_task(&ctx, &mm, fx_task.id, None, None).await?;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author_id, ctx.user_id());
+        assert_eq!(comments[0].content, "comment 01 edited");
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_delete_err_not_author() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let owner_ctx = Ctx::new(1000)?;
+        let fx_project_id =
+            seed_project(&owner_ctx, &mm, "test_update_delete_err_not_author project").await?;
+        let fx_task = seed_tasks(&owner_ctx, &mm, fx_project_id, &["task 01"])
+            .await?
+            .remove(0);
+        let comment_c = CommentForCreate {
+            task_id: fx_task.id,
+            content: "comment of root".to_string(),
+        };
+        let fx_id = CommentBmc::create(&root_ctx, &mm, comment_c).await?;
+
+        // -- Exec
+        let comment_u = CommentForUpdate {
+            content: Some("edited".to_string()),
+        };
+        let update_res = CommentBmc::update(&owner_ctx, &mm, fx_id, comment_u).await;
+        let delete_res = CommentBmc::delete(&owner_ctx, &mm, fx_id).await;
+
+        // -- Check
+        assert!(
+            matches!(update_res, Err(Error::AccessDenied { entity: "comment", id }) if id == fx_id),
+            "update should be denied to non author"
+        );
+        assert!(
+            matches!(delete_res, Err(Error::AccessDenied { entity: "comment", id }) if id == fx_id),
+            "delete should be denied to non author"
+        );
+        let comment = CommentBmc::get(&owner_ctx, &mm, fx_id).await?;
+        assert_eq!(comment.content, "comment of root");
+
+        // -- Clean
+        ProjectBmc::delete(&owner_ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use super::store;
+use super::{migrate, store};
 
 use derive_more::From;
 use lib_auth::pwd;
@@ -13,15 +13,67 @@ pub enum Error {
         max: i64,
         actual: i64,
     },
+    ListLimitUnderMin {
+        min: i64,
+        actual: i64,
+    },
+    ListOrderByInvalid(String),
+    BulkOverMax {
+        max: usize,
+        actual: usize,
+    },
+    BulkFilterEmpty,
+    ListCursorInvalid,
+    ListCursorWithOffset,
     EntityNotFound {
         entity: &'static str,
         id: i64,
     },
+    UpsertKeyMissing {
+        entity: &'static str,
+    },
+    SearchNotSupported {
+        entity: &'static str,
+    },
+    VersionConflict {
+        entity: &'static str,
+        id: i64,
+        expected: i64,
+    },
+    TaskParentCycle {
+        id: i64,
+        parent_id: i64,
+    },
+    TaskParentOtherProject {
+        parent_id: i64,
+        project_id: i64,
+    },
+    TaskDependencyCycle {
+        task_id: i64,
+        blocker_id: i64,
+    },
+    TaskBlocked {
+        id: i64,
+    },
+    RecurrenceInvalid {
+        rule: String,
+        reason: String,
+    },
+    AccessDenied {
+        entity: &'static str,
+        id: i64,
+    },
+    UniqueViolation {
+        table: String,
+        constraint: String,
+    },
     // Modules
     #[from]
     Pwd(pwd::Error),
     #[from]
     Store(store::Error),
+    #[from]
+    Migrate(migrate::Error),
 
     // External Errors
     #[from]
@@ -32,6 +84,22 @@ pub enum Error {
     ModqlIntoSea(#[serde_as(as = "DisplayFromStr")] modql::filter::IntoSeaError),
 }
 
+impl Error {
+    /// Resolves a sqlx unique constraint violation to `Error::UniqueViolation`
+    /// (other errors are returned as is).
+    pub fn resolve_unique_violation(self) -> Self {
+        if let Error::Sqlx(sqlx::Error::Database(db_error)) = &self {
+            if db_error.is_unique_violation() {
+                return Error::UniqueViolation {
+                    table: db_error.table().unwrap_or_default().to_string(),
+                    constraint: db_error.constraint().unwrap_or_default().to_string(),
+                };
+            }
+        }
+        self
+    }
+}
+
 impl core::fmt::Display for Error {
     fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
         write!(fmt, "{self:?}")
@@ -0,0 +1,205 @@
+//! Change history of the entities with `DbBmc::has_history()`.
+//!
+//! - The `base` updates and deletes of those entities lock the matching rows, make the change,
+//!   and `record` the diff of each row, in the same transaction.
+//! - A `History` holds only the changed columns, with their values `before` and `after` the
+//!   change (the `after` of a `purge` is empty), the user (`cid`) and the time (`ctime`).
+//! - The bookkeeping columns (timestamps, `version`, `search_vector`) are not recorded, and an
+//!   update changing none of the other columns is not recorded.
+//!
+//! NOTE: Only the `base` writes are recorded, not the ones of the Bmc specific queries
+//!       (e.g., the task dependencies).
+
+use lib_utils::time::now_utc;
+use modql::field::{Fields, HasFields};
+use sea_query::{Expr, Iden, IntoIden, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use serde_with::serde_as;
+use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::Result;
+
+use super::base::{ChangeKind, DbBmc, JsonRows, TimestampIden};
+use super::ModelManager;
+
+/// The columns not recorded in the history.
+const SKIPPED_COLUMNS: &[&str] = &["cid", "ctime", "mid", "mtime", "version", "search_vector"];
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Fields, Serialize)]
+pub struct History {
+    pub id: i64,
+    pub entity: String,
+    pub entity_id: i64,
+    /// `update`, `delete`, `restore` or `purge`.
+    pub op: String,
+    pub before: Value,
+    pub after: Value,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+}
+
+#[derive(Iden)]
+enum HistoryIden {
+    #[iden = "history"]
+    Table,
+    Id,
+    Entity,
+    EntityId,
+    Op,
+    Before,
+    After,
+}
+
+/// Records the `kind` change of the `before` rows by the `Ctx` user, given the rows `after`
+/// the change (none for the deleted rows).
+/// (Nothing to record for a `Create`)
+pub(super) async fn record<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    kind: ChangeKind,
+    before: &JsonRows,
+    after: &JsonRows,
+) -> Result<()>
+where
+    MC: DbBmc,
+{
+    let op = match kind {
+        ChangeKind::Create => return Ok(()),
+        ChangeKind::Update => "update",
+        ChangeKind::Delete => "delete",
+        ChangeKind::Restore => "restore",
+        ChangeKind::Purge => "purge",
+    };
+
+    // -- Build the history rows.
+    let now = now_utc();
+    let mut query = Query::insert();
+    query.into_table(HistoryIden::Table).columns([
+        HistoryIden::Entity.into_iden(),
+        HistoryIden::EntityId.into_iden(),
+        HistoryIden::Op.into_iden(),
+        HistoryIden::Before.into_iden(),
+        HistoryIden::After.into_iden(),
+        TimestampIden::Cid.into_iden(),
+        TimestampIden::Ctime.into_iden(),
+    ]);
+    let mut count = 0;
+    for (id, row_before) in before {
+        let row_after = after
+            .iter()
+            .find(|(after_id, _)| after_id == id)
+            .map(|(_, row)| row);
+        let (diff_before, diff_after) = diff(row_before, row_after);
+        if kind == ChangeKind::Update && diff_before.is_empty() {
+            continue;
+        }
+
+        query.values([
+            MC::TABLE.into(),
+            (*id).into(),
+            op.into(),
+            Value::Object(diff_before).into(),
+            Value::Object(diff_after).into(),
+            ctx.user_id().into(),
+            now.into(),
+        ])?;
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(());
+    }
+
+    // -- Exec
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    mm.dbx().execute(sqlx_query).await?;
+
+    Ok(())
+}
+
+/// Returns the history of the entity (oldest first).
+/// NOTE: The caller must check that the entity is visible to the `Ctx` user.
+pub(super) async fn list_for_entity<MC>(mm: &ModelManager, entity_id: i64) -> Result<Vec<History>>
+where
+    MC: DbBmc,
+{
+    let mut query = Query::select();
+    query
+        .from(HistoryIden::Table)
+        .columns(History::field_column_refs())
+        .and_where(Expr::col(HistoryIden::Entity).eq(MC::TABLE))
+        .and_where(Expr::col(HistoryIden::EntityId).eq(entity_id))
+        .order_by(HistoryIden::Id, Order::Asc);
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_as_with::<_, History, _>(&sql, values);
+    let histories = mm.dbx().fetch_all(sqlx_query).await?;
+
+    Ok(histories)
+}
+
+/// Returns the columns of the `before` row changed in the `after` row (`None` when the row
+/// has been deleted), with their `before` and `after` values.
+/// (The columns missing from the `after` row are only in the `before` diff)
+fn diff(before: &Value, after: Option<&Value>) -> (Map<String, Value>, Map<String, Value>) {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut diff_before = Map::new();
+    let mut diff_after = Map::new();
+    for (column, value) in before {
+        if SKIPPED_COLUMNS.contains(&column.as_str()) {
+            continue;
+        }
+        match after.get(column) {
+            Some(after_value) if after_value == value => {}
+            Some(after_value) => {
+                diff_before.insert(column.clone(), value.clone());
+                diff_after.insert(column.clone(), after_value.clone());
+            }
+            None => {
+                diff_before.insert(column.clone(), value.clone());
+            }
+        }
+    }
+
+    (diff_before, diff_after)
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_ok() -> Result<()> {
+        let before = json!({"id": 1, "title": "t 01", "done": false, "mtime": "a", "version": 1});
+        let after = json!({"id": 1, "title": "t 02", "done": false, "mtime": "b", "version": 2});
+
+        let (diff_before, diff_after) = diff(&before, Some(&after));
+        assert_eq!(Value::Object(diff_before), json!({"title": "t 01"}));
+        assert_eq!(Value::Object(diff_after), json!({"title": "t 02"}));
+
+        let (diff_before, diff_after) = diff(&before, None);
+        assert_eq!(
+            Value::Object(diff_before),
+            json!({"id": 1, "title": "t 01", "done": false})
+        );
+        assert!(diff_after.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+use modql::field::{Fields, HasFields};
+use modql::filter::{
+    FilterNode, FilterNodeOptions, FilterNodes, ListOptions, OpVal, OpValValue, OpValsInt64,
+    OpValsString, OpValsValue, SeaResult, ToSeaConditionFn, ToSeaConditionFnHolder,
+};
+use modql::SIden;
+use sea_query::{
+    ColumnRef, ConditionExpression, Expr, Iden, OnConflict, PostgresQueryBuilder, Query,
+    SelectStatement,
+};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::{Error, Result};
+
+use super::base::{self, CommonIden, DbBmc};
+use super::modql_utils::time_to_sea_value;
+use super::task::TaskBmc;
+use super::{ListPage, ModelManager, PageOptions};
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Fields, Serialize)]
+pub struct Label {
+    pub id: i64,
+    pub owner_id: i64,
+    pub name: String,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+    pub mid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize)]
+pub struct LabelForCreate {
+    pub name: String,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct LabelForUpdate {
+    pub name: Option<String>,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct LabelFilter {
+    id: Option<OpValsInt64>,
+    name: Option<OpValsString>,
+
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+#[derive(Iden)]
+enum TaskLabelIden {
+    #[iden = "task_label"]
+    Table,
+    TaskId,
+    LabelId,
+}
+
+/// Label Backend Model Controller
+pub struct LabelBmc;
+
+impl DbBmc for LabelBmc {
+    const TABLE: &'static str = "label";
+
+    fn has_owner_id() -> bool {
+        true
+    }
+
+    fn has_timestamps() -> bool {
+        true
+    }
+}
+
+impl LabelBmc {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, label_c: LabelForCreate) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, label_c)
+            .await
+            .map_err(Error::resolve_unique_violation)
+    }
+
+    pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Label> {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<LabelFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Label>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn list_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<LabelFilter>>,
+        list_options: Option<ListOptions>,
+        page_options: PageOptions,
+    ) -> Result<ListPage<Label>> {
+        base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options).await
+    }
+
+    pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, data: LabelForUpdate) -> Result<()> {
+        base::update::<Self, _>(ctx, mm, id, data)
+            .await
+            .map_err(Error::resolve_unique_violation)
+    }
+
+    /// NOTE: Deleting a label also detaches it from all of its tasks (`ON DELETE CASCADE`).
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::delete::<Self>(ctx, mm, id).await
+    }
+
+    /// Attaches the label to the task (no-op if already attached).
+    /// (Only by the owner of the task, see `TaskBmc::check_owner`)
+    pub async fn attach(ctx: &Ctx, mm: &ModelManager, task_id: i64, label_id: i64) -> Result<()> {
+        TaskBmc::check_owner(ctx, mm, task_id).await?;
+        // Make sure the label is visible to the `Ctx` user.
+        Self::get(ctx, mm, label_id).await?;
+
+        let mut query = Query::insert();
+        query
+            .into_table(TaskLabelIden::Table)
+            .columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+            .values([task_id.into(), label_id.into()])?
+            .on_conflict(
+                OnConflict::columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+                    .do_nothing()
+                    .to_owned(),
+            );
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Detaches the label from the task (no-op if not attached).
+    /// (Only by the owner of the task, see `TaskBmc::check_owner`)
+    pub async fn detach(ctx: &Ctx, mm: &ModelManager, task_id: i64, label_id: i64) -> Result<()> {
+        TaskBmc::check_owner(ctx, mm, task_id).await?;
+        // Make sure the label is visible to the `Ctx` user.
+        Self::get(ctx, mm, label_id).await?;
+
+        let mut query = Query::delete();
+        query
+            .from_table(TaskLabelIden::Table)
+            .and_where(Expr::col(TaskLabelIden::TaskId).eq(task_id))
+            .and_where(Expr::col(TaskLabelIden::LabelId).eq(label_id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Returns the labels of the `Ctx` user on the task, ordered by name.
+    pub async fn list_for_task(ctx: &Ctx, mm: &ModelManager, task_id: i64) -> Result<Vec<Label>> {
+        // Make sure the task is visible to the `Ctx` user.
+        TaskBmc::get(ctx, mm, task_id).await?;
+
+        let mut query = Query::select();
+        query
+            .from(Self::table_ref())
+            .columns(Label::field_column_refs())
+            .and_where(
+                Expr::col(CommonIden::Id).in_subquery(
+                    Query::select()
+                        .column(TaskLabelIden::LabelId)
+                        .from(TaskLabelIden::Table)
+                        .and_where(Expr::col(TaskLabelIden::TaskId).eq(task_id))
+                        .to_owned(),
+                ),
+            )
+            .order_by(SIden("name"), sea_query::Order::Asc);
+        if !ctx.is_root() {
+            query.and_where(Expr::col(CommonIden::OwnerId).eq(ctx.user_id()));
+        }
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, Label, _>(&sql, values);
+        let labels = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(labels)
+    }
+}
+
+/// Attaches the labels of the `from_task_id` task to the `to_task_id` task.
+pub(in crate::model) async fn copy_task_labels(
+    mm: &ModelManager,
+    from_task_id: i64,
+    to_task_id: i64,
+) -> Result<()> {
+    let mut query = Query::insert();
+    query
+        .into_table(TaskLabelIden::Table)
+        .columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+        .select_from(
+            Query::select()
+                .expr(Expr::val(to_task_id))
+                .column(TaskLabelIden::LabelId)
+                .from(TaskLabelIden::Table)
+                .and_where(Expr::col(TaskLabelIden::TaskId).eq(from_task_id))
+                .to_owned(),
+        )?
+        .on_conflict(
+            OnConflict::columns([TaskLabelIden::TaskId, TaskLabelIden::LabelId])
+                .do_nothing()
+                .to_owned(),
+        );
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    mm.dbx().execute(sqlx_query).await?;
+
+    Ok(())
+}
+
+// -- Task filter nodes
+
+/// Returns the `TaskFilter` node matching the tasks with any (`all == false`) or all
+/// (`all == true`) of the `label_ids`.
+pub(in crate::model) fn task_label_ids_node(
+    name: &str,
+    label_ids: Vec<i64>,
+    all: bool,
+) -> FilterNode {
+    let to_sea_condition: ToSeaConditionFn = if all {
+        task_has_all_labels
+    } else {
+        task_has_any_label
+    };
+
+    FilterNode {
+        context_path: None,
+        name: name.to_string(),
+        opvals: vec![OpVal::Value(OpValValue::Eq(label_ids.into()))],
+        options: FilterNodeOptions::default(),
+        for_sea_condition: Some(ToSeaConditionFnHolder::new(to_sea_condition).into()),
+    }
+}
+
+fn task_has_any_label(_col: &ColumnRef, op_val: OpValValue) -> SeaResult<ConditionExpression> {
+    let label_ids = label_ids_of(op_val);
+    let task_ids = task_ids_with_labels(&label_ids);
+
+    Ok(task_id_col().in_subquery(task_ids).into())
+}
+
+fn task_has_all_labels(_col: &ColumnRef, op_val: OpValValue) -> SeaResult<ConditionExpression> {
+    let label_ids = label_ids_of(op_val);
+    let count = label_ids.len() as i64;
+    let mut task_ids = task_ids_with_labels(&label_ids);
+    task_ids
+        .group_by_col(TaskLabelIden::TaskId)
+        .and_having(Expr::cust_with_values(
+            "count(DISTINCT label_id) = $1",
+            [count],
+        ));
+
+    Ok(task_id_col().in_subquery(task_ids).into())
+}
+
+/// The `id` of the listed task (qualified, as the subqueries are on `task_label`).
+fn task_id_col() -> Expr {
+    Expr::col((SIden(TaskBmc::TABLE), CommonIden::Id))
+}
+
+fn task_ids_with_labels(label_ids: &[i64]) -> SelectStatement {
+    Query::select()
+        .column(TaskLabelIden::TaskId)
+        .from(TaskLabelIden::Table)
+        .and_where(Expr::col(TaskLabelIden::LabelId).is_in(label_ids.iter().copied()))
+        .to_owned()
+}
+
+/// Returns the distinct label ids of the filter value.
+fn label_ids_of(op_val: OpValValue) -> Vec<i64> {
+    let mut label_ids: Vec<i64> = match op_val {
+        OpValValue::Eq(serde_json::Value::Array(values)) => {
+            values.iter().filter_map(|v| v.as_i64()).collect()
+        }
+        _ => Vec::new(),
+    };
+    label_ids.sort_unstable();
+    label_ids.dedup();
+    label_ids
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils::{self, seed_project, seed_tasks};
+    use crate::model::project::ProjectBmc;
+    use crate::model::task::TaskFilter;
+    use anyhow::Result;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_attach_and_filter_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_attach_and_filter_ok project").await?;
+        let fx_tasks =
+            seed_tasks(&ctx, &mm, fx_project_id, &["task 01", "task 02", "task 03"]).await?;
+        let mut fx_label_ids = Vec::new();
+        for name in [
+            "test_attach_and_filter_ok-bug",
+            "test_attach_and_filter_ok-ui",
+        ] {
+            let label_c = LabelForCreate {
+                name: name.to_string(),
+            };
+            fx_label_ids.push(LabelBmc::create(&ctx, &mm, label_c).await?);
+        }
+        let (bug, ui) = (fx_label_ids[0], fx_label_ids[1]);
+
+        // -- Exec: task 01 (bug, ui), task 02 (ui), task 03 (none)
+        LabelBmc::attach(&ctx, &mm, fx_tasks[0].id, bug).await?;
+        LabelBmc::attach(&ctx, &mm, fx_tasks[0].id, ui).await?;
+        LabelBmc::attach(&ctx, &mm, fx_tasks[0].id, ui).await?;
+        LabelBmc::attach(&ctx, &mm, fx_tasks[1].id, ui).await?;
+        LabelBmc::attach(&ctx, &mm, fx_tasks[2].id, bug).await?;
+        LabelBmc::detach(&ctx, &mm, fx_tasks[2].id, bug).await?;
+
+        // -- Check: duplicate name
+        let label_c = LabelForCreate {
+            name: "test_attach_and_filter_ok-ui".to_string(),
+        };
+        assert!(
+            matches!(
+                LabelBmc::create(&ctx, &mm, label_c).await,
+                Err(Error::UniqueViolation { constraint, .. }) if constraint == "uq_label_owner_id_name"
+            ),
+            "duplicate label name should fail with UniqueViolation"
+        );
+
+        // -- Check: labels of the task
+        let labels = LabelBmc::list_for_task(&ctx, &mm, fx_tasks[0].id).await?;
+        assert_eq!(labels.len(), 2);
+
+        // -- Check: filters
+        let fx_titles = |filter| list_titles(&ctx, &mm, fx_project_id, filter);
+        assert_eq!(
+            fx_titles(json!({"label_ids_any": [bug, ui]})).await?,
+            &["task 01", "task 02"]
+        );
+        assert_eq!(
+            fx_titles(json!({"label_ids_all": [bug, ui]})).await?,
+            &["task 01"]
+        );
+        assert_eq!(
+            fx_titles(json!({"label_ids_all": [bug, bug]})).await?,
+            &["task 01"]
+        );
+        assert_eq!(
+            fx_titles(json!({"label_ids_all": [ui], "title": "task 02"})).await?,
+            &["task 02"]
+        );
+        for filter in [json!({"label_ids_any": []}), json!({"label_ids_all": []})] {
+            assert_eq!(fx_titles(filter).await?, &["task 01", "task 02", "task 03"]);
+        }
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        for id in fx_label_ids {
+            LabelBmc::delete(&ctx, &mm, id).await?;
+        }
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_attach_err_not_task_owner() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let assignee_ctx = Ctx::new(1000)?;
+        let fx_project_id =
+            seed_project(&root_ctx, &mm, "test_attach_err_not_task_owner project").await?;
+        let fx_task_id = seed_tasks(&root_ctx, &mm, fx_project_id, &["task 01"]).await?[0].id;
+        TaskBmc::assign(&root_ctx, &mm, fx_task_id, Some(assignee_ctx.user_id())).await?;
+        let label_c = |name: &str| LabelForCreate {
+            name: format!("test_attach_err_not_task_owner-{name}"),
+        };
+        let fx_root_label_id = LabelBmc::create(&root_ctx, &mm, label_c("root")).await?;
+        let fx_assignee_label_id = LabelBmc::create(&assignee_ctx, &mm, label_c("mine")).await?;
+        LabelBmc::attach(&root_ctx, &mm, fx_task_id, fx_root_label_id).await?;
+
+        // -- Exec
+        let res = LabelBmc::attach(&assignee_ctx, &mm, fx_task_id, fx_assignee_label_id).await;
+
+        // -- Check: the assignee cannot label the task
+        assert!(
+            matches!(res, Err(Error::AccessDenied { entity: "task", id }) if id == fx_task_id),
+            "AccessDenied not matching"
+        );
+
+        // -- Check: the assignee only sees its own labels of the task
+        let labels = LabelBmc::list_for_task(&assignee_ctx, &mm, fx_task_id).await?;
+        assert!(labels.is_empty());
+        let labels = LabelBmc::list_for_task(&root_ctx, &mm, fx_task_id).await?;
+        assert_eq!(labels.len(), 1);
+
+        // -- Clean
+        ProjectBmc::delete(&root_ctx, &mm, fx_project_id).await?;
+        LabelBmc::delete(&root_ctx, &mm, fx_root_label_id).await?;
+        LabelBmc::delete(&assignee_ctx, &mm, fx_assignee_label_id).await?;
+        Ok(())
+    }
+
+    async fn list_titles(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: i64,
+        mut filter: serde_json::Value,
+    ) -> Result<Vec<String>> {
+        filter["project_id"] = json!(project_id);
+        let filters: Vec<TaskFilter> = serde_json::from_value(json!([filter]))?;
+        let tasks = TaskBmc::list(ctx, mm, Some(filters), None).await?;
+        Ok(tasks.into_iter().map(|t| t.title).collect())
+    }
+}
@@ -0,0 +1,252 @@
+//! Keyset (cursor) pagination support for `base::list_page`.
+//!
+//! - The `SortKey` is the `order_bys` of the list with the `id` appended as tiebreaker,
+//!   so that every row has a distinct position.
+//! - Each listed row also selects its sort key values as a json object, and the page
+//!   `next_cursor` is the b64u encoded json of the last row key values (and of the sort key).
+//! - The next page then only lists the rows after those key values. The key values are cast
+//!   back to the column types with `jsonb_populate_record`, so the cursor does not depend on
+//!   the last row still existing or being unchanged.
+//!
+//! - The rows with `NULL` in a sort column come last, for both directions (`NULLS LAST`),
+//!   and the `after_cond` compares the nullable key values accordingly.
+
+use lib_utils::b64::{b64u_decode_to_string, b64u_encode};
+use modql::filter::{OrderBy, OrderBys};
+use sea_query::{Alias, Condition, Expr, NullOrdering, Order, SelectStatement, SimpleExpr};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::model::{Error, Result};
+
+/// Alias of the selected sort key values column.
+pub const KEY_COLUMN: &str = "list_key";
+
+/// The ordered (column, ascending) of a list, ending with the `id` tiebreaker.
+pub struct SortKey(Vec<(String, bool)>);
+
+#[derive(Serialize, Deserialize)]
+struct ListCursor {
+    /// The `SortKey` the cursor was made for (e.g., `"title,!id"`).
+    sort: String,
+    /// The sort key values of the last row of the page, e.g., `{"title": "...", "id": 1003}`.
+    key: serde_json::Value,
+}
+
+/// An entity with its sort key values (json object text).
+pub struct Keyed<E> {
+    pub entity: E,
+    pub key: String,
+}
+
+impl<'r, E> FromRow<'r, PgRow> for Keyed<E>
+where
+    E: FromRow<'r, PgRow>,
+{
+    fn from_row(row: &'r PgRow) -> sqlx::Result<Self> {
+        Ok(Keyed {
+            entity: E::from_row(row)?,
+            key: row.try_get(KEY_COLUMN)?,
+        })
+    }
+}
+
+impl SortKey {
+    pub fn from_order_bys(order_bys: Option<&OrderBys>) -> Result<Self> {
+        let mut columns = Vec::new();
+        for order_by in order_bys.into_iter().flatten() {
+            let (column, asc) = match order_by {
+                OrderBy::Asc(column) => (column, true),
+                OrderBy::Desc(column) => (column, false),
+            };
+            let valid = !column.is_empty()
+                && column
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(Error::ListOrderByInvalid(column.to_string()));
+            }
+            columns.push((column.to_string(), asc));
+        }
+
+        if !columns.iter().any(|(column, _)| column == "id") {
+            columns.push(("id".to_string(), true));
+        }
+
+        Ok(SortKey(columns))
+    }
+
+    /// Orders the list query by the sort key (with the `id` tiebreaker), `NULLS LAST`.
+    pub fn apply_order(&self, query: &mut SelectStatement) {
+        for (column, asc) in &self.0 {
+            let order = if *asc { Order::Asc } else { Order::Desc };
+            query.order_by_with_nulls(Alias::new(column), order, NullOrdering::Last);
+        }
+    }
+
+    /// Returns the expression selecting the sort key values of a row as a json object text.
+    /// (Selected as `KEY_COLUMN`)
+    pub fn key_expr(&self) -> SimpleExpr {
+        // NOTE: Column names were validated as plain identifiers, and are bound as values
+        //       for the json keys.
+        let args: Vec<String> = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, (column, _))| format!("${}, \"{column}\"", i + 1))
+            .collect();
+        let sql = format!("jsonb_build_object({})::text", args.join(", "));
+        let names = self.0.iter().map(|(column, _)| column.clone());
+
+        Expr::cust_with_values(sql, names)
+    }
+
+    /// Returns the condition matching the rows after the `key` (json object text)
+    /// in the sort order, for the `table` rows.
+    ///
+    /// e.g., for `title,!due_date,id`:
+    ///   `title > k.title
+    ///    OR (title = k.title AND (due_date < k.due_date OR due_date IS NULL))
+    ///    OR (title = k.title AND due_date = k.due_date AND id > k.id)`
+    ///
+    /// A `NULL` key value is matched with `IS NULL`, and has no rows after it in its
+    /// column (`NULLS LAST`).
+    pub fn after_cond(&self, table: &str, key: &str) -> Result<Condition> {
+        let key_json: serde_json::Value =
+            serde_json::from_str(key).map_err(|_| Error::ListCursorInvalid)?;
+        let key_is_null = |column: &str| key_json.get(column).is_none_or(|v| v.is_null());
+        let key_value = |column: &str| {
+            Expr::cust_with_values(
+                format!("(SELECT \"{column}\" FROM jsonb_populate_record(NULL::\"{table}\", $1::jsonb))"),
+                [key.to_string()],
+            )
+        };
+
+        let mut any = Condition::any();
+        for (i, (column, asc)) in self.0.iter().enumerate() {
+            // Nothing after a NULL in its column, the NULLs being last.
+            if key_is_null(column) {
+                continue;
+            }
+
+            let mut all = Condition::all();
+            for (prev_column, _) in &self.0[..i] {
+                let prev_col = Expr::col(Alias::new(prev_column));
+                all = all.add(match key_is_null(prev_column) {
+                    true => prev_col.is_null(),
+                    false => prev_col.eq(key_value(prev_column)),
+                });
+            }
+            let col = Expr::col(Alias::new(column));
+            let after = match asc {
+                true => col.clone().gt(key_value(column)),
+                false => col.clone().lt(key_value(column)),
+            };
+            all = all.add(Condition::any().add(after).add(col.is_null()));
+            any = any.add(all);
+        }
+
+        Ok(any)
+    }
+
+    /// Returns the opaque cursor for the `key` (json object text) of the last row of a page.
+    pub fn encode_cursor(&self, key: &str) -> Result<String> {
+        let cursor = ListCursor {
+            sort: self.to_string(),
+            key: serde_json::from_str(key).map_err(|_| Error::ListCursorInvalid)?,
+        };
+        let json = serde_json::to_string(&cursor).map_err(|_| Error::ListCursorInvalid)?;
+
+        Ok(b64u_encode(json))
+    }
+
+    /// Returns the key (json object text) of the `cursor`, which must have been made for
+    /// this sort key.
+    pub fn decode_cursor(&self, cursor: &str) -> Result<String> {
+        let cursor: ListCursor = b64u_decode_to_string(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .ok_or(Error::ListCursorInvalid)?;
+        if cursor.sort != self.to_string() || !cursor.key.is_object() {
+            return Err(Error::ListCursorInvalid);
+        }
+
+        Ok(cursor.key.to_string())
+    }
+}
+
+impl core::fmt::Display for SortKey {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let columns: Vec<String> = self
+            .0
+            .iter()
+            .map(|(column, asc)| match asc {
+                true => column.to_string(),
+                false => format!("!{column}"),
+            })
+            .collect();
+        fmt.write_str(&columns.join(","))
+    }
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_sort_key_id_tiebreaker_ok() -> Result<()> {
+        let sort_key = SortKey::from_order_bys(Some(&OrderBys::from(vec!["title", "!mtime"])))?;
+        assert_eq!(sort_key.to_string(), "title,!mtime,id");
+
+        let sort_key = SortKey::from_order_bys(Some(&OrderBys::from(vec!["!id", "title"])))?;
+        assert_eq!(sort_key.to_string(), "!id,title");
+
+        let sort_key = SortKey::from_order_bys(None)?;
+        assert_eq!(sort_key.to_string(), "id");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_key_invalid_column_err() -> Result<()> {
+        let res = SortKey::from_order_bys(Some(&OrderBys::from("title; DROP TABLE task")));
+        assert!(
+            matches!(res, Err(Error::ListOrderByInvalid(_))),
+            "ListOrderByInvalid not matching"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_roundtrip_ok() -> Result<()> {
+        let sort_key = SortKey::from_order_bys(Some(&OrderBys::from("!title")))?;
+        let fx_key = r#"{"id": 1003, "title": "task 03"}"#;
+
+        let cursor = sort_key.encode_cursor(fx_key)?;
+        let key = sort_key.decode_cursor(&cursor)?;
+
+        let key: serde_json::Value = serde_json::from_str(&key)?;
+        assert_eq!(key, serde_json::from_str::<serde_json::Value>(fx_key)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor_other_sort_err() -> Result<()> {
+        let sort_key = SortKey::from_order_bys(Some(&OrderBys::from("!title")))?;
+        let cursor = sort_key.encode_cursor(r#"{"id": 1003, "title": "task 03"}"#)?;
+
+        let other_sort_key = SortKey::from_order_bys(Some(&OrderBys::from("title")))?;
+        assert!(matches!(
+            other_sort_key.decode_cursor(&cursor),
+            Err(Error::ListCursorInvalid)
+        ));
+        assert!(matches!(
+            sort_key.decode_cursor("not-a-cursor"),
+            Err(Error::ListCursorInvalid)
+        ));
+        Ok(())
+    }
+}
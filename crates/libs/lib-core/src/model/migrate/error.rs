@@ -0,0 +1,58 @@
+use derive_more::From;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+use crate::model::{script, store};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+    // -- Migration files
+    DirCantRead {
+        dir: String,
+        cause: String,
+    },
+    FileCantRead {
+        file: String,
+        cause: String,
+    },
+    FileNameInvalid(String),
+    VersionDuplicate(i64),
+    UpFileMissing(i64),
+    DownFileMissing(i64),
+
+    // -- Applied migrations
+    AppliedMigrationModified {
+        version: i64,
+        name: String,
+    },
+    AppliedMigrationFileMissing {
+        version: i64,
+        name: String,
+    },
+    MigrationsPending {
+        current: Option<i64>,
+        latest: i64,
+    },
+
+    // -- Modules
+    #[from]
+    Store(store::Error),
+    #[from]
+    Script(script::Error),
+
+    // -- Externals
+    #[from]
+    Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+// Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
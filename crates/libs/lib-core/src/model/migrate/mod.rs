@@ -0,0 +1,543 @@
+//! Versioned schema migrations.
+//!
+//! - A migration is a `NNNN_name.up.sql` file, with an optional `NNNN_name.down.sql` file to
+//!   revert it, in the migrations dir (`SERVICE_DB_MIGRATIONS_DIR`).
+//!   Migrations are applied in `NNNN` version order, each one in its own transaction
+//!   (see `script` for the supported SQL).
+//! - Applied migrations are recorded, with the checksum of their up file, in the
+//!   `schema_migration` table. An applied migration must never be edited (add a new one instead):
+//!   `up`, `down` and `verify` refuse to run if the checksum of an applied migration changed.
+//! - Concurrent runs (e.g., several service instances starting) are serialized with a
+//!   Postgres advisory lock.
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use lib_utils::b64::b64u_encode;
+use serde::Serialize;
+use serde_with::serde_as;
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, Executor, FromRow, Postgres};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tracing::info;
+
+use crate::core_config;
+use crate::model::script::exec_script;
+use crate::model::store::{new_db_pool, Db};
+
+const MIGRATION_TABLE: &str = "schema_migration";
+/// Key of the advisory lock held while migrating (any constant unique to this app).
+const MIGRATION_LOCK_KEY: i64 = 7_135_427_581;
+
+const UP_SUFFIX: &str = ".up.sql";
+const DOWN_SUFFIX: &str = ".down.sql";
+
+pub struct Migration {
+    pub version: i64,
+    pub name: String,
+    /// b64u encoded sha256 of the up file content.
+    pub checksum: String,
+    up: SqlFile,
+    down: Option<SqlFile>,
+}
+
+struct SqlFile {
+    name: String,
+    content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum MigrationState {
+    Pending,
+    Applied,
+    /// Applied, but its up file has been edited since.
+    Modified,
+    /// Applied, but its up file is no longer in the migrations dir.
+    FileMissing,
+}
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub applied_at: Option<OffsetDateTime>,
+}
+
+#[derive(FromRow)]
+struct AppliedMigration {
+    version: i64,
+    name: String,
+    checksum: String,
+    applied_at: OffsetDateTime,
+}
+
+pub struct Migrator {
+    db: Db,
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Creates the `Migrator` for the service database (`SERVICE_DB_URL`) and migrations dir
+    /// (`SERVICE_DB_MIGRATIONS_DIR`).
+    pub async fn init() -> Result<Self> {
+        let db = new_db_pool().await?;
+        Self::new(db, &core_config().DB_MIGRATIONS_DIR)
+    }
+
+    pub fn new(db: Db, dir: impl AsRef<Path>) -> Result<Self> {
+        let migrations = load_migrations(dir.as_ref())?;
+        Ok(Migrator { db, migrations })
+    }
+
+    /// Returns the migrations of the migrations dir, ordered by version.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Applies all the pending migrations and returns their versions.
+    pub async fn up(&self) -> Result<Vec<i64>> {
+        let mut conn = self.db.acquire().await?;
+
+        lock(&mut conn).await?;
+        let res = self.up_locked(&mut conn).await;
+        unlock(&mut conn).await?;
+
+        res
+    }
+
+    /// Reverts the last `steps` applied migrations and returns their versions.
+    pub async fn down(&self, steps: usize) -> Result<Vec<i64>> {
+        let mut conn = self.db.acquire().await?;
+
+        lock(&mut conn).await?;
+        let res = self.down_locked(&mut conn, steps).await;
+        unlock(&mut conn).await?;
+
+        res
+    }
+
+    /// Returns the state of every migration, either in the migrations dir or applied.
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>> {
+        let mut conn = self.db.acquire().await?;
+        let applied = applied_migrations(&mut conn).await?;
+
+        let mut statuses: BTreeMap<i64, MigrationStatus> = BTreeMap::new();
+        for migration in &self.migrations {
+            statuses.insert(
+                migration.version,
+                MigrationStatus {
+                    version: migration.version,
+                    name: migration.name.clone(),
+                    state: MigrationState::Pending,
+                    applied_at: None,
+                },
+            );
+        }
+        for applied in applied {
+            let state = match self.find(applied.version) {
+                Some(migration) if migration.checksum == applied.checksum => {
+                    MigrationState::Applied
+                }
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::FileMissing,
+            };
+            statuses.insert(
+                applied.version,
+                MigrationStatus {
+                    version: applied.version,
+                    name: applied.name,
+                    state,
+                    applied_at: Some(applied.applied_at),
+                },
+            );
+        }
+
+        Ok(statuses.into_values().collect())
+    }
+
+    /// Checks that all the migrations are applied and unmodified,
+    /// and returns the current schema version.
+    pub async fn verify(&self) -> Result<Option<i64>> {
+        let mut conn = self.db.acquire().await?;
+        let applied = applied_migrations(&mut conn).await?;
+        self.check_applied(&applied)?;
+
+        let current = applied.iter().map(|a| a.version).max();
+        let pending = self
+            .migrations
+            .iter()
+            .any(|m| !applied.iter().any(|a| a.version == m.version));
+        if let (true, Some(latest)) = (pending, self.migrations.last()) {
+            return Err(Error::MigrationsPending {
+                current,
+                latest: latest.version,
+            });
+        }
+
+        Ok(current)
+    }
+}
+
+// -- Migrator Privates
+
+impl Migrator {
+    fn find(&self, version: i64) -> Option<&Migration> {
+        self.migrations.iter().find(|m| m.version == version)
+    }
+
+    /// Fails if an applied migration has been edited or removed from the migrations dir.
+    fn check_applied(&self, applied: &[AppliedMigration]) -> Result<()> {
+        for applied in applied {
+            match self.find(applied.version) {
+                Some(migration) if migration.checksum == applied.checksum => (),
+                Some(_) => {
+                    return Err(Error::AppliedMigrationModified {
+                        version: applied.version,
+                        name: applied.name.clone(),
+                    })
+                }
+                None => {
+                    return Err(Error::AppliedMigrationFileMissing {
+                        version: applied.version,
+                        name: applied.name.clone(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn up_locked(&self, conn: &mut PoolConnection<Postgres>) -> Result<Vec<i64>> {
+        create_migration_table(conn).await?;
+        let applied = applied_migrations(conn).await?;
+        self.check_applied(&applied)?;
+
+        let mut versions = Vec::new();
+        for migration in &self.migrations {
+            if applied.iter().any(|a| a.version == migration.version) {
+                continue;
+            }
+
+            let mut txn = conn.begin().await?;
+            exec_script(&mut txn, &migration.up.name, &migration.up.content).await?;
+            sqlx::query(&format!(
+                "INSERT INTO {MIGRATION_TABLE} (version, name, checksum) VALUES ($1, $2, $3)"
+            ))
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(&migration.checksum)
+            .execute(&mut *txn)
+            .await?;
+            txn.commit().await?;
+
+            info!(
+                "{:<12} - up {:04}_{}",
+                "MIGRATE", migration.version, migration.name
+            );
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+
+    async fn down_locked(
+        &self,
+        conn: &mut PoolConnection<Postgres>,
+        steps: usize,
+    ) -> Result<Vec<i64>> {
+        create_migration_table(conn).await?;
+        let applied = applied_migrations(conn).await?;
+        self.check_applied(&applied)?;
+
+        let mut versions = Vec::new();
+        for applied in applied.iter().rev().take(steps) {
+            // NOTE: `check_applied` guarantees the migration is in the migrations dir.
+            let Some(migration) = self.find(applied.version) else {
+                continue;
+            };
+            let down = migration
+                .down
+                .as_ref()
+                .ok_or(Error::DownFileMissing(migration.version))?;
+
+            let mut txn = conn.begin().await?;
+            exec_script(&mut txn, &down.name, &down.content).await?;
+            sqlx::query(&format!("DELETE FROM {MIGRATION_TABLE} WHERE version = $1"))
+                .bind(migration.version)
+                .execute(&mut *txn)
+                .await?;
+            txn.commit().await?;
+
+            info!(
+                "{:<12} - down {:04}_{}",
+                "MIGRATE", migration.version, migration.name
+            );
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+}
+
+async fn lock(conn: &mut PoolConnection<Postgres>) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut **conn)
+        .await?;
+    Ok(())
+}
+
+async fn unlock(conn: &mut PoolConnection<Postgres>) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut **conn)
+        .await?;
+    Ok(())
+}
+
+async fn create_migration_table(conn: &mut PoolConnection<Postgres>) -> Result<()> {
+    let sql = format!(
+        "CREATE TABLE IF NOT EXISTS {MIGRATION_TABLE} (
+          version BIGINT PRIMARY KEY,
+          name VARCHAR(256) NOT NULL,
+          checksum VARCHAR(64) NOT NULL,
+          applied_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+        )"
+    );
+    (&mut **conn).execute(sql.as_str()).await?;
+    Ok(())
+}
+
+/// Returns the applied migrations ordered by version
+/// (none if the migration table does not exist yet).
+async fn applied_migrations(conn: &mut PoolConnection<Postgres>) -> Result<Vec<AppliedMigration>> {
+    let (table_exists,) = sqlx::query_as::<_, (bool,)>("SELECT to_regclass($1) IS NOT NULL")
+        .bind(MIGRATION_TABLE)
+        .fetch_one(&mut **conn)
+        .await?;
+    if !table_exists {
+        return Ok(Vec::new());
+    }
+
+    let applied = sqlx::query_as::<_, AppliedMigration>(&format!(
+        "SELECT version, name, checksum, applied_at FROM {MIGRATION_TABLE} ORDER BY version"
+    ))
+    .fetch_all(&mut **conn)
+    .await?;
+
+    Ok(applied)
+}
+
+// -- Migration Files
+
+#[derive(Debug, PartialEq)]
+enum FileKind {
+    Up,
+    Down,
+}
+
+/// Parses a `NNNN_name.up.sql` or `NNNN_name.down.sql` file name.
+fn parse_file_name(file_name: &str) -> Option<(i64, &str, FileKind)> {
+    let (stem, kind) = if let Some(stem) = file_name.strip_suffix(UP_SUFFIX) {
+        (stem, FileKind::Up)
+    } else {
+        (file_name.strip_suffix(DOWN_SUFFIX)?, FileKind::Down)
+    };
+
+    let (version, name) = stem.split_once('_')?;
+    if name.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let version = version.parse::<i64>().ok()?;
+
+    Some((version, name, kind))
+}
+
+/// Loads the migrations of the `dir`, ordered by version.
+/// (Files not ending with `.sql` are ignored)
+fn load_migrations(dir: &Path) -> Result<Vec<Migration>> {
+    let read_dir = fs::read_dir(dir).map_err(|ex| Error::DirCantRead {
+        dir: dir.to_string_lossy().to_string(),
+        cause: ex.to_string(),
+    })?;
+
+    // version -> (name, up, down)
+    type Files = (String, Option<SqlFile>, Option<SqlFile>);
+    let mut files_by_version: BTreeMap<i64, Files> = BTreeMap::new();
+
+    for entry in read_dir {
+        let path = entry
+            .map_err(|ex| Error::DirCantRead {
+                dir: dir.to_string_lossy().to_string(),
+                cause: ex.to_string(),
+            })?
+            .path();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !file_name.ends_with(".sql") {
+            continue;
+        }
+
+        let (version, name, kind) =
+            parse_file_name(&file_name).ok_or_else(|| Error::FileNameInvalid(file_name.clone()))?;
+        let content = fs::read_to_string(&path).map_err(|ex| Error::FileCantRead {
+            file: path.to_string_lossy().to_string(),
+            cause: ex.to_string(),
+        })?;
+
+        let files = files_by_version
+            .entry(version)
+            .or_insert_with(|| (name.to_string(), None, None));
+        let slot = match kind {
+            FileKind::Up => &mut files.1,
+            FileKind::Down => &mut files.2,
+        };
+        if files.0 != name || slot.is_some() {
+            return Err(Error::VersionDuplicate(version));
+        }
+        *slot = Some(SqlFile {
+            name: file_name.clone(),
+            content,
+        });
+    }
+
+    files_by_version
+        .into_iter()
+        .map(|(version, (name, up, down))| {
+            let up = up.ok_or(Error::UpFileMissing(version))?;
+            Ok(Migration {
+                version,
+                name,
+                checksum: b64u_encode(Sha256::digest(up.content.as_bytes())),
+                up,
+                down,
+            })
+        })
+        .collect()
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_parse_file_name_ok() -> Result<()> {
+        assert_eq!(
+            parse_file_name("0001_initial-schema.up.sql"),
+            Some((1, "initial-schema", FileKind::Up))
+        );
+        assert_eq!(
+            parse_file_name("0012_task_labels.down.sql"),
+            Some((12, "task_labels", FileKind::Down))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_file_name_invalid() -> Result<()> {
+        for fx_file_name in [
+            "0001_initial.sql",
+            "initial.up.sql",
+            "00a1_initial.up.sql",
+            "0001_.up.sql",
+            "_initial.up.sql",
+        ] {
+            assert_eq!(parse_file_name(fx_file_name), None, "{fx_file_name}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_migrations_ok() -> Result<()> {
+        let fx_dir = fx_migrations_dir(
+            "test_load_migrations_ok",
+            &[
+                ("0002_second.up.sql", "SELECT 2;"),
+                ("0001_first.up.sql", "SELECT 1;"),
+                ("0001_first.down.sql", "SELECT -1;"),
+                ("README.md", "not a migration"),
+            ],
+        )?;
+
+        let migrations = load_migrations(&fx_dir)?;
+
+        let versions: Vec<_> = migrations
+            .iter()
+            .map(|m| (m.version, m.name.as_str()))
+            .collect();
+        assert_eq!(versions, &[(1, "first"), (2, "second")]);
+        let down = migrations[0].down.as_ref().map(|f| f.content.as_str());
+        assert_eq!(down, Some("SELECT -1;"));
+        assert!(migrations[1].down.is_none());
+        assert_ne!(migrations[0].checksum, migrations[1].checksum);
+
+        fs::remove_dir_all(fx_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_migrations_err_duplicate() -> Result<()> {
+        let fx_dir = fx_migrations_dir(
+            "test_load_migrations_err_duplicate",
+            &[
+                ("0001_first.up.sql", "SELECT 1;"),
+                ("0001_other.up.sql", "SELECT 1;"),
+            ],
+        )?;
+
+        let res = load_migrations(&fx_dir);
+        assert!(
+            matches!(res, Err(Error::VersionDuplicate(1))),
+            "VersionDuplicate not matching"
+        );
+
+        fs::remove_dir_all(fx_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_migrations_err_up_missing() -> Result<()> {
+        let fx_dir = fx_migrations_dir(
+            "test_load_migrations_err_up_missing",
+            &[("0003_third.down.sql", "SELECT -3;")],
+        )?;
+
+        let res = load_migrations(&fx_dir);
+        assert!(
+            matches!(res, Err(Error::UpFileMissing(3))),
+            "UpFileMissing not matching"
+        );
+
+        fs::remove_dir_all(fx_dir)?;
+        Ok(())
+    }
+
+    fn fx_migrations_dir(name: &str, files: &[(&str, &str)]) -> Result<std::path::PathBuf> {
+        let dir = std::env::temp_dir().join(format!("lib_core-migrate-{name}"));
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        for (file_name, content) in files {
+            fs::write(dir.join(file_name), content)?;
+        }
+        Ok(dir)
+    }
+}
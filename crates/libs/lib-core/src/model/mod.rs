@@ -13,34 +13,88 @@
 //!     - ModelManager is designed to be passed as an argument to all Model Controller functions.
 mod base;
 mod error;
+mod list_cursor;
+mod modql_utils;
 mod store;
 
+pub mod comment;
+pub mod history;
+pub mod label;
+pub mod migrate;
+pub mod outbox;
 pub mod project;
+pub mod recurrence;
+pub mod script;
 pub mod task;
 pub mod user;
 
+pub use self::base::{ListPage, PageOptions, SearchHit, Upserted};
 pub use self::error::{Error, Result};
+pub use self::modql_utils::Clearable;
 
-use self::store::{new_db_pool, Db};
+use crate::core_config;
 
-/// **ModelManager** implements clone since it only contains a database `Pool` which is simply a
-/// reference-counted handle to the inner pool state; it can be cloned cheaply.
+use self::migrate::Migrator;
+use self::store::{new_db_pool, Dbx};
+
+/// **ModelManager** implements clone since it only contains a `Dbx`, which holds the database
+/// `Pool` (a reference-counted handle to the inner pool state) and, for a transactional
+/// `ModelManager`, a reference-counted handle to its transaction; it can be cloned cheaply.
 #[derive(Clone)]
 pub struct ModelManager {
-    db: Db,
+    dbx: Dbx,
 }
 
 impl ModelManager {
     /// Constructor
+    /// Fails if the database schema is not at the latest migration version (see `migrate`).
     pub async fn init() -> Result<Self> {
         let db = new_db_pool().await?;
+        Migrator::new(db.clone(), &core_config().DB_MIGRATIONS_DIR)?
+            .verify()
+            .await?;
+
+        Ok(ModelManager { dbx: Dbx::new(db) })
+    }
+
+    /// Returns a `ModelManager` running all the Bmc calls made with it in one transaction,
+    /// which must be ended with `commit_txn` or `rollback_txn`.
+    /// (If `self` is already transactional, the transaction is shared)
+    pub fn new_with_txn(&self) -> ModelManager {
+        ModelManager {
+            dbx: self.dbx.with_txn(),
+        }
+    }
+
+    pub fn is_txn(&self) -> bool {
+        self.dbx.is_txn()
+    }
+
+    /// Commits the transaction (no-op if nothing has been executed in it).
+    pub async fn commit_txn(&self) -> Result<()> {
+        self.dbx.commit().await?;
+        Ok(())
+    }
+
+    /// Rolls back the transaction (no-op if nothing has been executed in it).
+    pub async fn rollback_txn(&self) -> Result<()> {
+        self.dbx.rollback().await?;
+        Ok(())
+    }
 
-        Ok(ModelManager { db })
+    /// Commits the transaction of the `mm_txn` made by `self.new_with_txn()`, unless `self` is
+    /// transactional (the transaction is then shared, and ended by the owner of `self`).
+    /// For the Bmc functions making several calls atomically.
+    pub async fn commit_txn_of(&self, mm_txn: &ModelManager) -> Result<()> {
+        if !self.is_txn() {
+            mm_txn.commit_txn().await?;
+        }
+        Ok(())
     }
 
-    /// Returns the sqlx db pool reference
+    /// Returns the query executor
     /// (Only for the model layer)
-    pub(in crate::model) fn db(&self) -> &Db {
-        &self.db
+    pub(in crate::model) fn dbx(&self) -> &Dbx {
+        &self.dbx
     }
 }
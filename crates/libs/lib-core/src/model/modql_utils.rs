@@ -0,0 +1,73 @@
+//! Helper functions to use with `modql` attributes (e.g., `#[modql(to_sea_value_fn = "...")]`),
+//! and the `Clearable` update field values.
+
+use lib_utils::time::{date_format, parse_date, parse_utc};
+use modql::filter::{IntoSeaError, SeaResult};
+use sea_query::{Nullable, Value};
+use serde::{Deserialize, Deserializer};
+use time::Date;
+
+/// The value of an optional column in a `...ForUpdate` (`Fields`) struct, as an
+/// `Option<Clearable<T>>`: `None` leaves the column unchanged, and `Some(Clearable(None))`
+/// clears it (`NULL`).
+///
+/// Deserialized from a missing field (`None`), `null` (cleared) or a value, with
+/// `#[serde(default, deserialize_with = "clearable")]` (or `clearable_date`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clearable<T>(pub Option<T>);
+
+impl<T> From<Clearable<T>> for Value
+where
+    T: Into<Value> + Nullable,
+{
+    fn from(val: Clearable<T>) -> Self {
+        val.0.into()
+    }
+}
+
+impl<T> Nullable for Clearable<T>
+where
+    T: Nullable,
+{
+    fn null() -> Value {
+        T::null()
+    }
+}
+
+pub fn clearable<'de, D, T>(deserializer: D) -> Result<Option<Clearable<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(|val| Some(Clearable(val)))
+}
+
+/// Same as `clearable`, for the `YYYY-MM-DD` dates (see `date_format`).
+pub fn clearable_date<'de, D>(deserializer: D) -> Result<Option<Clearable<Date>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    date_format::option::deserialize(deserializer).map(|val| Some(Clearable(val)))
+}
+
+/// Converts a json Rfc3339 string into a sea-query time value.
+/// Used by the filters on timestamp columns (e.g., `"ctime": {"$gte": "2024-01-01T00:00:00Z"}`).
+pub fn time_to_sea_value(json_value: serde_json::Value) -> SeaResult<sea_query::Value> {
+    let moment = json_value
+        .as_str()
+        .ok_or_else(|| IntoSeaError::custom(format!("time value is not a string: {json_value}")))?;
+    let time = parse_utc(moment).map_err(|ex| IntoSeaError::custom(ex.to_string()))?;
+
+    Ok(time.into())
+}
+
+/// Converts a json `YYYY-MM-DD` string into a sea-query date value.
+/// Used by the filters on date columns (e.g., `"due_date": {"$lt": "2024-02-01"}`).
+pub fn date_to_sea_value(json_value: serde_json::Value) -> SeaResult<sea_query::Value> {
+    let moment = json_value
+        .as_str()
+        .ok_or_else(|| IntoSeaError::custom(format!("date value is not a string: {json_value}")))?;
+    let date = parse_date(moment).map_err(|ex| IntoSeaError::custom(ex.to_string()))?;
+
+    Ok(date.into())
+}
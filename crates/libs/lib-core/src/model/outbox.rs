@@ -0,0 +1,236 @@
+//! Domain events of the entities with `DbBmc::has_events()` (transactional outbox).
+//!
+//! - The `base` writes of those entities add their events to the `outbox` table in the same
+//!   transaction as the change, so an event exists if and only if its change is committed.
+//! - An event is named after the entity and the change: `TaskCreated`, `TaskUpdated`,
+//!   `TaskDeleted`, `TaskRestored` or `TaskPurged`.
+//! - Its `data` is the entity row after the change (before it, for a deleted row), and its
+//!   `before` the entity row before the change (none for a created row).
+//! - The pending events are delivered to the subscribers by the `event::Dispatcher`.
+//!
+//! NOTE: Only the `base` writes emit events, not the ones of the Bmc specific queries
+//!       (e.g., the task dependencies).
+
+use lib_utils::time::now_utc;
+use modql::field::{Fields, HasFields};
+use sea_query::{Expr, Iden, IntoIden, LockBehavior, LockType, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_json::Value;
+use serde_with::serde_as;
+use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::Result;
+
+use super::base::{ChangeKind, DbBmc, JsonRows, TimestampIden};
+use super::ModelManager;
+
+/// The number of failed deliveries after which an event is no longer delivered
+/// (kept in the outbox with its `last_error`).
+pub const ATTEMPTS_MAX: i32 = 10;
+
+/// The generated columns not in the event `data`.
+const SKIPPED_COLUMNS: &[&str] = &["search_vector"];
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Fields, Serialize)]
+pub struct Event {
+    pub id: i64,
+    /// e.g., `TaskCreated`
+    pub name: String,
+    pub entity: String,
+    pub entity_id: i64,
+    pub data: Value,
+    pub before: Option<Value>,
+    /// The number of failed deliveries of the event so far.
+    pub attempts: i32,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+}
+
+impl Event {
+    /// Returns `true` if the entity row of the event, after or before the change, is visible to
+    /// the `Ctx` user, i.e., the user is its owner or assignee (see `DbBmc::has_assignee_id`),
+    /// or if the `Ctx` is the root `Ctx`.
+    /// (e.g., the previous assignee of an unassigned task still receives its event)
+    pub fn is_visible_to(&self, ctx: &Ctx) -> bool {
+        ctx.is_root()
+            || [Some(&self.data), self.before.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|row| {
+                    ["owner_id", "assignee_id"].iter().any(|column| {
+                        row.get(column).and_then(Value::as_i64) == Some(ctx.user_id())
+                    })
+                })
+    }
+}
+
+#[derive(Iden)]
+enum OutboxIden {
+    #[iden = "outbox"]
+    Table,
+    Id,
+    Name,
+    Entity,
+    EntityId,
+    Data,
+    Before,
+    ProcessedAt,
+    Attempts,
+    LastError,
+}
+
+/// Outbox Backend Model Controller
+///
+/// NOTE: For the event dispatchers only (system level, no `Ctx`).
+pub struct OutboxBmc;
+
+impl OutboxBmc {
+    /// Returns the oldest pending events (up to the `limit`), locked until the end of the
+    /// transaction of the `mm` and skipped by the other transactions meanwhile.
+    /// (The `mm` must be transactional, see `ModelManager::new_with_txn`)
+    pub async fn lock_pending(mm: &ModelManager, limit: u64) -> Result<Vec<Event>> {
+        let mut query = Query::select();
+        query
+            .from(OutboxIden::Table)
+            .columns(Event::field_column_refs())
+            .and_where(Expr::col(OutboxIden::ProcessedAt).is_null())
+            .and_where(Expr::col(OutboxIden::Attempts).lt(ATTEMPTS_MAX))
+            .order_by(OutboxIden::Id, Order::Asc)
+            .limit(limit)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, Event, _>(&sql, values);
+        let events = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(events)
+    }
+
+    /// Marks the events as delivered.
+    pub async fn mark_processed(mm: &ModelManager, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut query = Query::update();
+        query
+            .table(OutboxIden::Table)
+            .value(OutboxIden::ProcessedAt, now_utc())
+            .and_where(Expr::col(OutboxIden::Id).is_in(ids.iter().copied()));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Records a failed delivery of the event (delivered again until `ATTEMPTS_MAX`).
+    pub async fn mark_failed(mm: &ModelManager, id: i64, error: &str) -> Result<()> {
+        let mut query = Query::update();
+        query
+            .table(OutboxIden::Table)
+            .value(OutboxIden::Attempts, Expr::col(OutboxIden::Attempts).add(1))
+            .value(OutboxIden::LastError, error)
+            .and_where(Expr::col(OutboxIden::Id).eq(id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+}
+
+/// Adds the events of the `kind` change of the entities of the `ids` by the `Ctx` user, given
+/// their rows `before` (if any) and `after` (none for the deleted rows) the change.
+pub(super) async fn record<MC>(
+    ctx: &Ctx,
+    mm: &ModelManager,
+    kind: ChangeKind,
+    ids: &[i64],
+    before: &JsonRows,
+    after: &JsonRows,
+) -> Result<()>
+where
+    MC: DbBmc,
+{
+    let change = match kind {
+        ChangeKind::Create => "Created",
+        ChangeKind::Update => "Updated",
+        ChangeKind::Delete => "Deleted",
+        ChangeKind::Restore => "Restored",
+        ChangeKind::Purge => "Purged",
+    };
+    let name = format!("{}{change}", pascal_case(MC::TABLE));
+
+    let now = now_utc();
+    let mut query = Query::insert();
+    query.into_table(OutboxIden::Table).columns([
+        OutboxIden::Name.into_iden(),
+        OutboxIden::Entity.into_iden(),
+        OutboxIden::EntityId.into_iden(),
+        OutboxIden::Data.into_iden(),
+        OutboxIden::Before.into_iden(),
+        TimestampIden::Cid.into_iden(),
+        TimestampIden::Ctime.into_iden(),
+    ]);
+    for id in ids {
+        let row = after
+            .iter()
+            .chain(before.iter())
+            .find(|(row_id, _)| row_id == id)
+            .map(|(_, row)| event_data(row))
+            .unwrap_or(Value::Null);
+        let before_row = before
+            .iter()
+            .find(|(row_id, _)| row_id == id)
+            .map(|(_, row)| event_data(row));
+
+        query.values([
+            name.as_str().into(),
+            MC::TABLE.into(),
+            (*id).into(),
+            row.into(),
+            before_row.into(),
+            ctx.user_id().into(),
+            now.into(),
+        ])?;
+    }
+
+    let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+    let sqlx_query = sqlx::query_with(&sql, values);
+    mm.dbx().execute(sqlx_query).await?;
+
+    Ok(())
+}
+
+/// Returns the entity `row` without the `SKIPPED_COLUMNS`.
+fn event_data(row: &Value) -> Value {
+    let mut row = row.clone();
+    if let Some(columns) = row.as_object_mut() {
+        columns.retain(|column, _| !SKIPPED_COLUMNS.contains(&column.as_str()));
+    }
+    row
+}
+
+/// Returns the `snake_case` name in `PascalCase` (e.g., `task_label` -> `TaskLabel`).
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
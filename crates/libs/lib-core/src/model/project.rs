@@ -0,0 +1,235 @@
+use modql::field::Fields;
+use modql::filter::{FilterNodes, ListOptions, OpValsInt64, OpValsString, OpValsValue};
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::ctx::Ctx;
+use crate::model::Result;
+
+use super::base::{self, DbBmc};
+use super::modql_utils::time_to_sea_value;
+use super::task::TaskBmc;
+use super::{ListPage, ModelManager, PageOptions};
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Fields, Serialize)]
+pub struct Project {
+    pub id: i64,
+    pub owner_id: i64,
+    pub name: String,
+
+    pub version: i64,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+    pub mid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub mtime: OffsetDateTime,
+}
+
+#[derive(Fields, Deserialize)]
+pub struct ProjectForCreate {
+    pub name: String,
+}
+
+#[derive(Fields, Default, Deserialize)]
+pub struct ProjectForUpdate {
+    pub name: Option<String>,
+}
+
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+pub struct ProjectFilter {
+    id: Option<OpValsInt64>,
+    name: Option<OpValsString>,
+
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+}
+
+/// Project Backend Model Controller
+pub struct ProjectBmc;
+
+impl DbBmc for ProjectBmc {
+    const TABLE: &'static str = "project";
+
+    fn has_owner_id() -> bool {
+        true
+    }
+
+    fn has_timestamps() -> bool {
+        true
+    }
+
+    fn has_version() -> bool {
+        true
+    }
+
+    fn has_events() -> bool {
+        true
+    }
+}
+
+impl ProjectBmc {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, project_c: ProjectForCreate) -> Result<i64> {
+        base::create::<Self, _>(ctx, mm, project_c).await
+    }
+
+    pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Project> {
+        base::get::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<ProjectFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Project>> {
+        base::list::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn list_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<ProjectFilter>>,
+        list_options: Option<ListOptions>,
+        page_options: PageOptions,
+    ) -> Result<ListPage<Project>> {
+        base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options).await
+    }
+
+    pub async fn update(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        data: ProjectForUpdate,
+    ) -> Result<()> {
+        base::update::<Self, _>(ctx, mm, id, data).await
+    }
+
+    /// Same as `update`, but fails with `Error::VersionConflict` if the project `version`
+    /// is no longer the provided one.
+    pub async fn update_with_version(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        version: Option<i64>,
+        data: ProjectForUpdate,
+    ) -> Result<()> {
+        base::update_with_version::<Self, _>(ctx, mm, id, version, data).await
+    }
+
+    /// Deletes the project and all its tasks (live or in the trash), in the same transaction.
+    /// NOTE: The tasks go through `TaskBmc` (not `ON DELETE CASCADE`), so their deletion is
+    ///       recorded in their history and events.
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let mm_txn = mm.new_with_txn();
+        TaskBmc::purge_of_project(ctx, &mm_txn, id).await?;
+        base::delete::<Self>(ctx, &mm_txn, id).await?;
+        mm.commit_txn_of(&mm_txn).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::_dev_utils;
+    use crate::model::history;
+
+    use super::*;
+    use crate::model::Error;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_name = "test_create_ok project";
+
+        let id = ProjectBmc::create(
+            &ctx,
+            &mm,
+            ProjectForCreate {
+                name: fx_name.to_string(),
+            },
+        )
+        .await?;
+
+        let project = ProjectBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(project.name, fx_name);
+        assert_eq!(project.owner_id, ctx.user_id());
+
+        ProjectBmc::delete(&ctx, &mm, id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_error_not_found() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_id = 100;
+
+        let res = ProjectBmc::get(&ctx, &mm, fx_id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "project",
+                    id: 100
+                })
+            ),
+            "EntityNotFound not matching"
+        );
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_purges_tasks_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id =
+            _dev_utils::seed_project(&ctx, &mm, "test_delete_purges_tasks_ok project").await?;
+        let fx_tasks = _dev_utils::seed_tasks(
+            &ctx,
+            &mm,
+            fx_project_id,
+            &[
+                "test_delete_purges_tasks_ok task 01",
+                "test_delete_purges_tasks_ok task 02",
+            ],
+        )
+        .await?;
+        let (fx_live_id, fx_trashed_id) = (fx_tasks[0].id, fx_tasks[1].id);
+        TaskBmc::delete(&ctx, &mm, fx_trashed_id).await?;
+
+        // -- Exec
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        // -- Check: the tasks are purged through `TaskBmc`
+        for id in [fx_live_id, fx_trashed_id] {
+            let res = TaskBmc::get_deleted(&ctx, &mm, id).await;
+            assert!(
+                matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+                "Task should have been purged with its project"
+            );
+        }
+        let history = history::list_for_entity::<TaskBmc>(&mm, fx_live_id).await?;
+        let ops: Vec<&str> = history.iter().map(|h| h.op.as_str()).collect();
+        assert_eq!(ops, &["delete", "purge"]);
+
+        Ok(())
+    }
+}
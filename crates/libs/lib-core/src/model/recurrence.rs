@@ -0,0 +1,216 @@
+//! Recurrence rules of the recurring tasks (see `TaskBmc::update`).
+//!
+//! - A rule is a subset of the iCalendar `RRULE`: `FREQ=DAILY|WEEKLY|MONTHLY|YEARLY` with an
+//!   optional `INTERVAL=N` (every N days, weeks, ...), e.g., `FREQ=WEEKLY;INTERVAL=2`.
+//! - Parts are separated by `;`, and are case insensitive. An `RRULE:` prefix is accepted.
+//! - Rules are stored in their normalized form (see `Display`), e.g., `FREQ=WEEKLY`.
+//!
+//! NOTE: Monthly and yearly occurrences falling on a day the month does not have
+//!       (e.g., the 31st, or February 29th) are moved to the last day of the month.
+
+use core::fmt;
+use core::str::FromStr;
+
+use time::{Date, Duration, Month};
+
+use crate::model::{Error, Result};
+
+/// The max `INTERVAL` of a rule.
+const INTERVAL_MAX: u32 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// The number of `frequency` periods between two occurrences (>= 1).
+    pub interval: u32,
+}
+
+impl Recurrence {
+    /// Returns the date of the occurrence following the one of the `date`,
+    /// or `None` if out of the supported date range.
+    pub fn next_date(&self, date: Date) -> Option<Date> {
+        let interval = i64::from(self.interval);
+        match self.frequency {
+            Frequency::Daily => date.checked_add(Duration::days(interval)),
+            Frequency::Weekly => date.checked_add(Duration::weeks(interval)),
+            Frequency::Monthly => add_months(date, interval),
+            Frequency::Yearly => add_months(date, interval * 12),
+        }
+    }
+}
+
+/// Adds the `months` to the `date`, clamping the day to the last day of the resulting month.
+fn add_months(date: Date, months: i64) -> Option<Date> {
+    let month_index = i64::from(date.year()) * 12 + i64::from(u8::from(date.month())) - 1 + months;
+    let year = i32::try_from(month_index.div_euclid(12)).ok()?;
+    let month = Month::try_from(u8::try_from(month_index.rem_euclid(12) + 1).ok()?).ok()?;
+    let day = date.day().min(month.length(year));
+
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::RecurrenceInvalid {
+            rule: rule.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let trimmed = rule.trim();
+        let parts = match trimmed.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &trimmed[6..],
+            _ => trimmed,
+        };
+
+        let mut frequency = None;
+        let mut interval = None;
+        for part in parts.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(&format!("'{part}' is not NAME=VALUE")))?;
+            let value = value.trim();
+
+            match name.trim().to_ascii_uppercase().as_str() {
+                "FREQ" if frequency.is_some() => return Err(invalid("FREQ is repeated")),
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(invalid(&format!("FREQ '{value}' is not supported"))),
+                    });
+                }
+                "INTERVAL" if interval.is_some() => return Err(invalid("INTERVAL is repeated")),
+                "INTERVAL" => {
+                    interval = match value.parse::<u32>() {
+                        Ok(n) if (1..=INTERVAL_MAX).contains(&n) => Some(n),
+                        _ => {
+                            return Err(invalid(&format!(
+                                "INTERVAL must be a number from 1 to {INTERVAL_MAX}"
+                            )))
+                        }
+                    };
+                }
+                other => return Err(invalid(&format!("'{other}' is not supported"))),
+            }
+        }
+
+        Ok(Recurrence {
+            frequency: frequency.ok_or_else(|| invalid("FREQ is missing"))?,
+            interval: interval.unwrap_or(1),
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={frequency}")?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        Ok(())
+    }
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use time::macros::date;
+
+    #[test]
+    fn test_parse_normalize_ok() -> Result<()> {
+        let fx_rules = [
+            ("FREQ=WEEKLY", "FREQ=WEEKLY"),
+            ("freq=daily;interval=1", "FREQ=DAILY"),
+            ("RRULE:INTERVAL=3; FREQ=MONTHLY;", "FREQ=MONTHLY;INTERVAL=3"),
+            (" FREQ=YEARLY;INTERVAL=2 ", "FREQ=YEARLY;INTERVAL=2"),
+        ];
+
+        for (rule, normalized) in fx_rules {
+            let recurrence: Recurrence = rule.parse()?;
+            assert_eq!(recurrence.to_string(), normalized);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_err_invalid() -> Result<()> {
+        let fx_rules = [
+            "",
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=1000",
+            "FREQ=DAILY;INTERVAL=x",
+            "FREQ=DAILY;FREQ=WEEKLY",
+            "FREQ=WEEKLY;BYDAY=MO",
+            "WEEKLY",
+        ];
+
+        for rule in fx_rules {
+            let res = rule.parse::<Recurrence>();
+            assert!(
+                matches!(res, Err(Error::RecurrenceInvalid { .. })),
+                "'{rule}' should be invalid"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_date_ok() -> Result<()> {
+        let fx_cases = [
+            ("FREQ=DAILY", date!(2024 - 02 - 28), date!(2024 - 02 - 29)),
+            (
+                "FREQ=DAILY;INTERVAL=3",
+                date!(2024 - 12 - 30),
+                date!(2025 - 01 - 02),
+            ),
+            (
+                "FREQ=WEEKLY;INTERVAL=2",
+                date!(2024 - 03 - 01),
+                date!(2024 - 03 - 15),
+            ),
+            ("FREQ=MONTHLY", date!(2024 - 01 - 31), date!(2024 - 02 - 29)),
+            (
+                "FREQ=MONTHLY;INTERVAL=3",
+                date!(2024 - 11 - 15),
+                date!(2025 - 02 - 15),
+            ),
+            ("FREQ=YEARLY", date!(2024 - 02 - 29), date!(2025 - 02 - 28)),
+        ];
+
+        for (rule, date, expected) in fx_cases {
+            let recurrence: Recurrence = rule.parse()?;
+            assert_eq!(
+                recurrence.next_date(date),
+                Some(expected),
+                "{rule} from {date}"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use derive_more::From;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+    FileCantRead {
+        file: String,
+        cause: String,
+    },
+    /// A string, quoted identifier, comment or dollar-quoted body opened at `line`
+    /// is never closed.
+    Unterminated {
+        file: String,
+        line: usize,
+        token: String,
+    },
+    StatementFail {
+        file: String,
+        line: usize,
+        cause: String,
+    },
+
+    // -- Externals
+    #[from]
+    Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+// Error Boilerplate
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::result::Result<(), core::fmt::Error> {
+        write!(fmt, "{self:?}")
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,361 @@
+//! SQL script (file) execution.
+//!
+//! - `split_sql` splits a script into its statements on the `;` that are not inside a
+//!   string (`'...'`, `E'...'`), a quoted identifier (`"..."`), a comment (`-- ...`, `/* ... */`)
+//!   or a dollar-quoted body (`$$ ... $$`, `$tag$ ... $tag$`), so `DO` blocks, plpgsql functions
+//!   and triggers can be part of a script.
+//! - Each statement keeps the line it starts at, reported when its execution fails.
+//! - `exec_file` runs each statement on its own, while `exec_file_in_txn` runs the whole
+//!   file in one transaction.
+//!
+//! NOTE: SQL-standard function bodies (`BEGIN ATOMIC ... END`) are not supported;
+//!       use a dollar-quoted body instead.
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::fs;
+use std::path::Path;
+
+use sqlx::{Executor, PgConnection};
+use tracing::debug;
+
+use crate::model::store::Db;
+
+#[derive(Debug, PartialEq)]
+pub struct SqlStatement<'a> {
+    pub sql: &'a str,
+    /// 1-based line of the first character of the statement in the script.
+    pub line: usize,
+}
+
+/// Executes the statements of the `file` one after the other, each in its own implicit
+/// transaction (required for statements like `CREATE DATABASE`).
+pub async fn exec_file(db: &Db, file: &Path) -> Result<()> {
+    debug!("{:<12} - exec_file: {file:?}", "SCRIPT");
+
+    let (file_label, content) = read_file(file)?;
+    let mut conn = db.acquire().await?;
+    exec_script(&mut conn, &file_label, &content).await
+}
+
+/// Executes all the statements of the `file` in one transaction.
+pub async fn exec_file_in_txn(db: &Db, file: &Path) -> Result<()> {
+    debug!("{:<12} - exec_file_in_txn: {file:?}", "SCRIPT");
+
+    let (file_label, content) = read_file(file)?;
+    let mut txn = db.begin().await?;
+    exec_script(&mut txn, &file_label, &content).await?;
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Executes the statements of the `content` script on the `conn`
+/// (e.g., a transaction the caller commits).
+/// `file` is only used to report errors.
+pub async fn exec_script(conn: &mut PgConnection, file: &str, content: &str) -> Result<()> {
+    let statements = split_sql(file, content)?;
+
+    for statement in statements {
+        conn.execute(statement.sql)
+            .await
+            .map_err(|ex| Error::StatementFail {
+                file: file.to_string(),
+                line: statement.line,
+                cause: ex.to_string(),
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Splits the `content` script into its (non-empty) statements, without their ending `;`.
+/// `file` is only used to report errors.
+pub fn split_sql<'a>(file: &str, content: &'a str) -> Result<Vec<SqlStatement<'a>>> {
+    let bytes = content.as_bytes();
+    let unterminated = |line: usize, token: &[u8]| Error::Unterminated {
+        file: file.to_string(),
+        line,
+        token: String::from_utf8_lossy(token).to_string(),
+    };
+
+    let mut statements = Vec::new();
+    // (offset, line) of the first character of the current statement.
+    let mut start: Option<(usize, usize)> = None;
+    let mut line = 1;
+    let mut i = 0;
+
+    // NOTE: All the delimiters are ASCII, which never appear inside a UTF-8 multi-byte
+    //       character, so scanning the bytes is safe and the offsets are char boundaries.
+    while i < bytes.len() {
+        let b = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        // -- Statement separators, whitespaces and comments.
+        match (b, next) {
+            (b';', _) => {
+                if let Some((offset, line)) = start.take() {
+                    statements.push(SqlStatement {
+                        sql: content[offset..i].trim_end(),
+                        line,
+                    });
+                }
+                i += 1;
+                continue;
+            }
+            (b'-', Some(b'-')) => {
+                i = find(bytes, i, b"\n").unwrap_or(bytes.len());
+                continue;
+            }
+            (b'/', Some(b'*')) => {
+                let end = block_comment_end(bytes, i).ok_or_else(|| unterminated(line, b"/*"))?;
+                line += count_lines(&bytes[i..end]);
+                i = end;
+                continue;
+            }
+            _ if b.is_ascii_whitespace() => {
+                if b == b'\n' {
+                    line += 1;
+                }
+                i += 1;
+                continue;
+            }
+            _ => (),
+        }
+
+        if start.is_none() {
+            start = Some((i, line));
+        }
+
+        // -- Quoted content.
+        let end = match b {
+            b'\'' => {
+                // `E'...'` strings support backslash escapes.
+                let escapes = i > 0
+                    && matches!(bytes[i - 1], b'E' | b'e')
+                    && (i < 2 || !is_ident_byte(bytes[i - 2]));
+                quoted_end(bytes, i, b'\'', escapes).ok_or_else(|| unterminated(line, b"'"))?
+            }
+            b'"' => quoted_end(bytes, i, b'"', false).ok_or_else(|| unterminated(line, b"\""))?,
+            b'$' => match dollar_tag_end(bytes, i) {
+                Some(tag_end) => {
+                    let tag = &bytes[i..tag_end];
+                    find(bytes, tag_end, tag)
+                        .map(|close| close + tag.len())
+                        .ok_or_else(|| unterminated(line, tag))?
+                }
+                None => i + 1,
+            },
+            _ => i + 1,
+        };
+        line += count_lines(&bytes[i..end]);
+        i = end;
+    }
+
+    if let Some((offset, line)) = start {
+        statements.push(SqlStatement {
+            sql: content[offset..].trim_end(),
+            line,
+        });
+    }
+
+    Ok(statements)
+}
+
+// -- Privates
+
+fn read_file(file: &Path) -> Result<(String, String)> {
+    let file_label = file.to_string_lossy().to_string();
+    let content = fs::read_to_string(file).map_err(|ex| Error::FileCantRead {
+        file: file_label.clone(),
+        cause: ex.to_string(),
+    })?;
+
+    Ok((file_label, content))
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+fn count_lines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|b| **b == b'\n').count()
+}
+
+/// Returns the offset of the first `pattern` at or after `from`.
+fn find(bytes: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+    bytes[from..]
+        .windows(pattern.len())
+        .position(|w| w == pattern)
+        .map(|pos| from + pos)
+}
+
+/// Returns the offset right after the closing `quote` of the string or identifier opened at
+/// `open`. A doubled `quote` is an escaped one.
+fn quoted_end(bytes: &[u8], open: usize, quote: u8, backslash_escapes: bool) -> Option<usize> {
+    let mut i = open + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote => {
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return Some(i + 1);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Returns the offset right after the `*/` closing the (possibly nested) block comment
+/// opened at `open`.
+fn block_comment_end(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// If a dollar-quote tag (`$$` or `$tag$`) opens at `open`, returns the offset right after it.
+/// (`$1` parameters and `$` inside identifiers are not tags)
+fn dollar_tag_end(bytes: &[u8], open: usize) -> Option<usize> {
+    if open > 0 && is_ident_byte(bytes[open - 1]) {
+        return None;
+    }
+    if bytes.get(open + 1).is_some_and(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut i = open + 1;
+    while i < bytes.len() && is_ident_byte(bytes[i]) {
+        i += 1;
+    }
+
+    (bytes.get(i) == Some(&b'$')).then_some(i + 1)
+}
+
+// -- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn sqls<'a>(statements: &[SqlStatement<'a>]) -> Vec<(&'a str, usize)> {
+        statements.iter().map(|s| (s.sql, s.line)).collect()
+    }
+
+    #[test]
+    fn test_split_sql_simple_ok() -> Result<()> {
+        let fx_content = "SELECT 1;\n\nSELECT 2 ;\n  SELECT 3";
+
+        let statements = split_sql("fx.sql", fx_content)?;
+
+        assert_eq!(
+            sqls(&statements),
+            &[("SELECT 1", 1), ("SELECT 2", 3), ("SELECT 3", 4)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_sql_quotes_ok() -> Result<()> {
+        let fx_content = r#"INSERT INTO "semi;colon" VALUES ('a;b', 'it''s;', E'x\';y');
+SELECT ';
+';"#;
+
+        let statements = split_sql("fx.sql", fx_content)?;
+
+        assert_eq!(
+            sqls(&statements),
+            &[
+                (
+                    r#"INSERT INTO "semi;colon" VALUES ('a;b', 'it''s;', E'x\';y')"#,
+                    1
+                ),
+                ("SELECT ';\n'", 2)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_sql_comments_ok() -> Result<()> {
+        let fx_content = "-- header; comment\n\
+                          /* block; /* nested; */ still; */\n\
+                          SELECT 1; -- trailing;\n\
+                          -- only a comment;\n";
+
+        let statements = split_sql("fx.sql", fx_content)?;
+
+        assert_eq!(sqls(&statements), &[("SELECT 1", 3)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_sql_dollar_quotes_ok() -> Result<()> {
+        let fx_content = r#"CREATE FUNCTION f() RETURNS trigger AS $body$
+BEGIN
+  NEW.x := 'a;b';
+  RETURN NEW;
+END;
+$body$ LANGUAGE plpgsql;
+
+DO $$ BEGIN PERFORM 1; END $$;
+SELECT $1, a$b FROM t;"#;
+
+        let statements = split_sql("fx.sql", fx_content)?;
+
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].sql.ends_with("$body$ LANGUAGE plpgsql"));
+        assert_eq!(
+            sqls(&statements[1..]),
+            &[
+                ("DO $$ BEGIN PERFORM 1; END $$", 8),
+                ("SELECT $1, a$b FROM t", 9)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_sql_err_unterminated() -> Result<()> {
+        for (fx_content, fx_token) in [
+            ("SELECT 1;\nSELECT 'abc;", "'"),
+            ("SELECT 1;\nSELECT \"abc;", "\""),
+            ("SELECT 1;\n/* abc;", "/*"),
+            ("SELECT 1;\nDO $x$ BEGIN END $y$;", "$x$"),
+        ] {
+            let res = split_sql("fx.sql", fx_content);
+            assert!(
+                matches!(
+                    &res,
+                    Err(Error::Unterminated { file, line: 2, token })
+                        if file == "fx.sql" && token == fx_token
+                ),
+                "Unterminated not matching for {fx_content:?} - {res:?}"
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+//! `Dbx` is the query executor of the `ModelManager`.
+//!
+//! It executes the queries either directly on the db pool, or, when created `with_txn`,
+//! on a single transaction shared by all of its clones. The transaction is begun on the
+//! first query and must be ended by `commit` or `rollback`
+//! (dropping the last clone without committing rolls it back).
+
+use std::sync::Arc;
+
+use sqlx::postgres::PgRow;
+use sqlx::query::{Query, QueryAs};
+use sqlx::{FromRow, IntoArguments, Postgres, Transaction};
+use tokio::sync::Mutex;
+
+use super::Db;
+
+type Txn = Transaction<'static, Postgres>;
+
+#[derive(Clone)]
+pub struct Dbx {
+    db: Db,
+    txn: Option<Arc<Mutex<Option<Txn>>>>,
+}
+
+impl Dbx {
+    pub fn new(db: Db) -> Self {
+        Dbx { db, txn: None }
+    }
+
+    /// Returns a transactional `Dbx` on the same pool.
+    /// If this `Dbx` is already transactional, the returned one shares its transaction.
+    pub fn with_txn(&self) -> Self {
+        let txn = self.txn.clone().unwrap_or_default();
+
+        Dbx {
+            db: self.db.clone(),
+            txn: Some(txn),
+        }
+    }
+
+    pub fn is_txn(&self) -> bool {
+        self.txn.is_some()
+    }
+
+    /// Commits the transaction, if one has been begun.
+    pub async fn commit(&self) -> Result<(), sqlx::Error> {
+        if let Some(txn) = self.take_txn().await {
+            txn.commit().await?;
+        }
+        Ok(())
+    }
+
+    /// Rolls back the transaction, if one has been begun.
+    pub async fn rollback(&self) -> Result<(), sqlx::Error> {
+        if let Some(txn) = self.take_txn().await {
+            txn.rollback().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn fetch_one<'q, O, A>(
+        &self,
+        query: QueryAs<'q, Postgres, O, A>,
+    ) -> Result<O, sqlx::Error>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        match &self.txn {
+            Some(txn) => {
+                let mut txn = txn.lock().await;
+                query
+                    .fetch_one(&mut **begin(&self.db, &mut txn).await?)
+                    .await
+            }
+            None => query.fetch_one(&self.db).await,
+        }
+    }
+
+    pub async fn fetch_optional<'q, O, A>(
+        &self,
+        query: QueryAs<'q, Postgres, O, A>,
+    ) -> Result<Option<O>, sqlx::Error>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        match &self.txn {
+            Some(txn) => {
+                let mut txn = txn.lock().await;
+                query
+                    .fetch_optional(&mut **begin(&self.db, &mut txn).await?)
+                    .await
+            }
+            None => query.fetch_optional(&self.db).await,
+        }
+    }
+
+    pub async fn fetch_all<'q, O, A>(
+        &self,
+        query: QueryAs<'q, Postgres, O, A>,
+    ) -> Result<Vec<O>, sqlx::Error>
+    where
+        O: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        match &self.txn {
+            Some(txn) => {
+                let mut txn = txn.lock().await;
+                query
+                    .fetch_all(&mut **begin(&self.db, &mut txn).await?)
+                    .await
+            }
+            None => query.fetch_all(&self.db).await,
+        }
+    }
+
+    /// Executes the query and returns the number of rows affected.
+    pub async fn execute<'q, A>(&self, query: Query<'q, Postgres, A>) -> Result<u64, sqlx::Error>
+    where
+        A: IntoArguments<'q, Postgres> + 'q,
+    {
+        let res = match &self.txn {
+            Some(txn) => {
+                let mut txn = txn.lock().await;
+                query
+                    .execute(&mut **begin(&self.db, &mut txn).await?)
+                    .await?
+            }
+            None => query.execute(&self.db).await?,
+        };
+        Ok(res.rows_affected())
+    }
+
+    async fn take_txn(&self) -> Option<Txn> {
+        match &self.txn {
+            Some(txn) => txn.lock().await.take(),
+            None => None,
+        }
+    }
+}
+
+/// Returns the open transaction of the `slot`, beginning a new one if needed.
+async fn begin<'a>(db: &Db, slot: &'a mut Option<Txn>) -> Result<&'a mut Txn, sqlx::Error> {
+    let txn = match slot.take() {
+        Some(txn) => txn,
+        None => db.begin().await?,
+    };
+    Ok(slot.insert(txn))
+}
@@ -1,9 +1,11 @@
+mod dbx;
 mod error;
 
 use std::time::Duration;
 
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 
+pub use self::dbx::Dbx;
 pub use self::error::{Error, Result};
 
 use crate::core_config;
@@ -1,54 +1,372 @@
-use modql::field::Fields;
-use modql::filter::{FilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString};
+use std::collections::{BTreeMap, HashMap};
+
+use lib_utils::time::{date_format, now_utc};
+use modql::field::{Field, Fields, HasFields};
+use modql::filter::{
+    FilterNode, FilterNodes, IntoFilterNodes, ListOptions, OpValsBool, OpValsInt64, OpValsString,
+    OpValsValue,
+};
+use modql::SIden;
+use sea_query::{
+    CommonTableExpression, Expr, Iden, OnConflict, Order, PostgresQueryBuilder, Query, UnionType,
+    WithClause,
+};
+use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use sqlx::prelude::FromRow;
+use time::format_description::well_known::Rfc3339;
+use time::{Date, OffsetDateTime};
 
 use crate::ctx::Ctx;
-use crate::model::Result;
+use crate::model::{Error, Result};
 
-use super::base::{self, DbBmc};
-use super::ModelManager;
+use super::base::{self, CommonIden, DbBmc};
+use super::history::{self, History};
+use super::label::{copy_task_labels, task_label_ids_node};
+use super::modql_utils::{
+    clearable, clearable_date, date_to_sea_value, time_to_sea_value, Clearable,
+};
+use super::project::ProjectBmc;
+use super::recurrence::Recurrence;
+use super::user::{User, UserBmc};
+use super::{ListPage, ModelManager, PageOptions, SearchHit, Upserted};
 
+#[serde_as]
 #[derive(Debug, Clone, FromRow, Fields, Serialize)]
 pub struct Task {
     pub id: i64,
+    pub owner_id: i64,
+    pub project_id: i64,
+    /// The user the task is assigned to (see `TaskBmc::assign`).
+    pub assignee_id: Option<i64>,
+    /// The parent task, in the same project (see `TaskBmc::set_parent`).
+    pub parent_id: Option<i64>,
+
     pub title: String,
+    pub description: Option<String>,
+    #[serde(with = "date_format::option")]
+    pub due_date: Option<Date>,
+    pub priority: TaskPriority,
     pub done: bool,
+    /// `true` if one of the tasks blocking this task is not done
+    /// (maintained by the db, see `TaskBmc::add_dependency`).
+    pub blocked: bool,
+    /// When the task was last marked as done (set by the db when `done` becomes `true`).
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub done_at: Option<OffsetDateTime>,
+    /// The reference of the task in an external tracker (see `TaskBmc::upsert`).
+    pub external_ref: Option<String>,
+    /// The recurrence rule of the task (see `recurrence`), e.g., `FREQ=WEEKLY`.
+    pub recurrence: Option<String>,
+    /// The next occurrence of the recurring task, created when the task was done.
+    pub next_occurrence_id: Option<i64>,
+
+    pub version: i64,
+
+    // Timestamps
+    pub cid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub ctime: OffsetDateTime,
+    pub mid: i64,
+    #[serde_as(as = "Rfc3339")]
+    pub mtime: OffsetDateTime,
+
+    // Soft delete
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
-#[derive(Fields, Deserialize)]
+/// The `task_priority` db enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "task_priority")]
+pub enum TaskPriority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl From<TaskPriority> for sea_query::Value {
+    fn from(val: TaskPriority) -> Self {
+        val.as_ref().into()
+    }
+}
+
+impl sea_query::Nullable for TaskPriority {
+    fn null() -> sea_query::Value {
+        sea_query::Value::String(None)
+    }
+}
+
+impl AsRef<str> for TaskPriority {
+    fn as_ref(&self) -> &str {
+        match self {
+            TaskPriority::Low => "Low",
+            TaskPriority::Medium => "Medium",
+            TaskPriority::High => "High",
+            TaskPriority::Urgent => "Urgent",
+        }
+    }
+}
+
+#[derive(Fields, Default, Deserialize)]
 pub struct TaskForCreate {
     pub title: String,
+    pub project_id: i64,
+    pub parent_id: Option<i64>,
+    pub description: Option<String>,
+    #[serde(default, with = "date_format::option")]
+    pub due_date: Option<Date>,
+    #[field(cast_as = "task_priority")]
+    pub priority: Option<TaskPriority>,
+    /// The recurrence rule (see `recurrence`), e.g., `FREQ=WEEKLY;INTERVAL=2`.
+    pub recurrence: Option<String>,
+}
+
+/// A task created or updated by its `external_ref` (see `TaskBmc::upsert`).
+#[derive(Fields, Deserialize)]
+pub struct TaskForUpsert {
+    pub external_ref: String,
+    pub project_id: i64,
+    pub title: String,
+    pub done: Option<bool>,
 }
 
 #[derive(Fields, Default, Deserialize)]
 pub struct TaskForUpdate {
     pub title: Option<String>,
+    /// `null` clears the description (see `Clearable`).
+    #[serde(default, deserialize_with = "clearable")]
+    pub description: Option<Clearable<String>>,
+    /// `null` clears the due date.
+    #[serde(default, deserialize_with = "clearable_date")]
+    pub due_date: Option<Clearable<Date>>,
+    #[field(cast_as = "task_priority")]
+    pub priority: Option<TaskPriority>,
     pub done: Option<bool>,
 }
 
-#[derive(FilterNodes, Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug)]
 pub struct TaskFilter {
+    #[serde(flatten)]
+    fields: TaskFieldsFilter,
+
+    /// The tasks with at least one of these labels.
+    label_ids_any: Option<Vec<i64>>,
+    /// The tasks with all of these labels.
+    label_ids_all: Option<Vec<i64>>,
+}
+
+/// The `TaskFilter` nodes on the task columns.
+#[derive(FilterNodes, Deserialize, Default, Debug)]
+struct TaskFieldsFilter {
     id: Option<OpValsInt64>,
+    project_id: Option<OpValsInt64>,
+    assignee_id: Option<OpValsInt64>,
+    parent_id: Option<OpValsInt64>,
     title: Option<OpValsString>,
+    description: Option<OpValsString>,
+    #[modql(to_sea_value_fn = "date_to_sea_value")]
+    due_date: Option<OpValsValue>,
+    #[modql(cast_as = "task_priority")]
+    priority: Option<OpValsString>,
     done: Option<OpValsBool>,
+    blocked: Option<OpValsBool>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    done_at: Option<OpValsValue>,
+    external_ref: Option<OpValsString>,
+    recurrence: Option<OpValsString>,
+
+    cid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    ctime: Option<OpValsValue>,
+    mid: Option<OpValsInt64>,
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    mtime: Option<OpValsValue>,
+
+    #[modql(to_sea_value_fn = "time_to_sea_value")]
+    deleted_at: Option<OpValsValue>,
+}
+
+impl IntoFilterNodes for TaskFilter {
+    fn filter_nodes(self, context_path: Option<String>) -> Vec<FilterNode> {
+        let mut nodes = self.fields.filter_nodes(context_path);
+        if let Some(label_ids) = self.label_ids_any.filter(|ids| !ids.is_empty()) {
+            nodes.push(task_label_ids_node("label_ids_any", label_ids, false));
+        }
+        if let Some(label_ids) = self.label_ids_all.filter(|ids| !ids.is_empty()) {
+            nodes.push(task_label_ids_node("label_ids_all", label_ids, true));
+        }
+        nodes
+    }
+}
+
+/// A task with all its (live) descendants.
+#[derive(Debug, Serialize)]
+pub struct TaskTree {
+    #[serde(flatten)]
+    pub task: Task,
+    /// The number of descendants (children, grandchildren, ...).
+    pub descendant_count: i64,
+    /// The number of descendants that are done.
+    pub descendant_done_count: i64,
+    pub children: Vec<TaskTree>,
+}
+
+impl TaskTree {
+    /// Builds the tree of the `task` from the `children` of each parent id.
+    fn build(task: Task, children: &mut HashMap<i64, Vec<Task>>) -> Self {
+        let children: Vec<TaskTree> = children
+            .remove(&task.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|child| TaskTree::build(child, children))
+            .collect();
+
+        let descendant_count = children.iter().map(|c| 1 + c.descendant_count).sum();
+        let descendant_done_count = children
+            .iter()
+            .map(|c| i64::from(c.task.done) + c.descendant_done_count)
+            .sum();
+
+        TaskTree {
+            task,
+            descendant_count,
+            descendant_done_count,
+            children,
+        }
+    }
+}
+
+#[derive(Iden)]
+enum TaskIden {
+    OwnerId,
+    ParentId,
+    Done,
+    Blocked,
+    Recurrence,
+    NextOccurrenceId,
+}
+
+#[derive(Iden)]
+enum TaskDependencyIden {
+    #[iden = "task_dependency"]
+    Table,
+    TaskId,
+    BlockerId,
 }
 
 pub struct TaskBmc;
 
 impl DbBmc for TaskBmc {
     const TABLE: &'static str = "task";
+
+    fn has_owner_id() -> bool {
+        true
+    }
+
+    fn has_assignee_id() -> bool {
+        true
+    }
+
+    fn has_timestamps() -> bool {
+        true
+    }
+
+    fn has_version() -> bool {
+        true
+    }
+
+    fn has_soft_delete() -> bool {
+        true
+    }
+
+    fn has_history() -> bool {
+        true
+    }
+
+    fn has_events() -> bool {
+        true
+    }
+
+    fn upsert_key() -> &'static [&'static str] {
+        &["owner_id", "external_ref"]
+    }
+
+    fn search_document() -> Option<&'static str> {
+        // NOTE: Must match the `task.search_vector` generated column.
+        Some("title || ' ' || coalesce(description, '')")
+    }
 }
 
 impl TaskBmc {
-    pub async fn create(ctx: &Ctx, mm: &ModelManager, task_c: TaskForCreate) -> Result<i64> {
+    pub async fn create(ctx: &Ctx, mm: &ModelManager, mut task_c: TaskForCreate) -> Result<i64> {
+        normalize_recurrence(&mut task_c.recurrence)?;
+        // Make sure the project exists and is visible to the `Ctx` user.
+        ProjectBmc::get(ctx, mm, task_c.project_id).await?;
+        if let Some(parent_id) = task_c.parent_id {
+            Self::check_parent(ctx, mm, task_c.project_id, parent_id).await?;
+        }
+
         base::create::<Self, _>(ctx, mm, task_c).await
     }
 
+    /// Creates all the tasks at once, and returns their ids in the `tasks_c` order.
+    pub async fn create_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        mut tasks_c: Vec<TaskForCreate>,
+    ) -> Result<Vec<i64>> {
+        for task_c in tasks_c.iter_mut() {
+            normalize_recurrence(&mut task_c.recurrence)?;
+        }
+        // Make sure the projects exist and are visible to the `Ctx` user.
+        let mut project_ids: Vec<i64> = tasks_c.iter().map(|t| t.project_id).collect();
+        project_ids.sort_unstable();
+        project_ids.dedup();
+        for project_id in project_ids {
+            ProjectBmc::get(ctx, mm, project_id).await?;
+        }
+        for task_c in tasks_c.iter() {
+            if let Some(parent_id) = task_c.parent_id {
+                Self::check_parent(ctx, mm, task_c.project_id, parent_id).await?;
+            }
+        }
+
+        base::create_many::<Self, _>(ctx, mm, tasks_c).await
+    }
+
+    /// Creates the task, or updates the `Ctx` user task with the same `external_ref`
+    /// (restoring it if it was in the trash).
+    /// Same `Error::TaskBlocked` and next occurrence as `update` when setting `done` on an
+    /// existing task.
+    pub async fn upsert(ctx: &Ctx, mm: &ModelManager, task_u: TaskForUpsert) -> Result<Upserted> {
+        // Make sure the project exists and is visible to the `Ctx` user.
+        ProjectBmc::get(ctx, mm, task_u.project_id).await?;
+
+        if task_u.done != Some(true) {
+            return base::upsert::<Self, _>(ctx, mm, task_u).await;
+        }
+
+        let mm_txn = mm.new_with_txn();
+        let upserted = base::upsert::<Self, _>(ctx, &mm_txn, task_u).await?;
+        Self::check_not_blocked(&mm_txn, &[upserted.id]).await?;
+        Self::create_next_occurrences(ctx, &mm_txn, &[upserted.id]).await?;
+        mm.commit_txn_of(&mm_txn).await?;
+
+        Ok(upserted)
+    }
+
     pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
         base::get::<Self, _>(ctx, mm, id).await
     }
 
+    /// Returns the tasks of the `ids` (ordered by id), skipping the ones not found.
+    pub async fn list_by_ids(ctx: &Ctx, mm: &ModelManager, ids: &[i64]) -> Result<Vec<Task>> {
+        base::list_by_ids::<Self, _>(ctx, mm, ids).await
+    }
+
     pub async fn list(
         ctx: &Ctx,
         mm: &ModelManager,
@@ -58,54 +376,1487 @@ impl TaskBmc {
         base::list::<Self, _, _>(ctx, mm, filters, list_options).await
     }
 
-    pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, data: TaskForUpdate) -> Result<()> {
-        base::update::<Self, _>(ctx, mm, id, data).await
-    }
+    pub async fn list_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<TaskFilter>>,
+        list_options: Option<ListOptions>,
+        page_options: PageOptions,
+    ) -> Result<ListPage<Task>> {
+        base::list_page::<Self, _, _>(ctx, mm, filters, list_options, page_options).await
+    }
+
+    /// Same as `list_page`, but only for the tasks assigned to the `Ctx` user.
+    pub async fn list_assigned_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<TaskFilter>>,
+        list_options: Option<ListOptions>,
+        page_options: PageOptions,
+    ) -> Result<ListPage<Task>> {
+        // Restrict every filter group to the `Ctx` user.
+        let filters = filters
+            .filter(|filters| !filters.is_empty())
+            .unwrap_or_else(|| vec![TaskFilter::default()])
+            .into_iter()
+            .map(|mut filter| {
+                filter.fields.assignee_id = Some(ctx.user_id().into());
+                filter
+            })
+            .collect::<Vec<_>>();
+
+        Self::list_page(ctx, mm, Some(filters), list_options, page_options).await
+    }
+
+    /// Returns the tasks matching the full-text `query` and the `filters`, most relevant first.
+    pub async fn search(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        query: &str,
+        filters: Option<Vec<TaskFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<SearchHit<Task>>> {
+        base::search::<Self, _, _>(ctx, mm, query, filters, list_options).await
+    }
+
+    pub async fn count(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<TaskFilter>>,
+    ) -> Result<i64> {
+        base::count::<Self, _>(ctx, mm, filters).await
+    }
+
+    /// NOTE: Fails with `Error::TaskBlocked` when setting `done` on a blocked task, and
+    ///       creates the next occurrence of a recurring task set `done` (in the same
+    ///       transaction). Same for all the updates.
+    pub async fn update(ctx: &Ctx, mm: &ModelManager, id: i64, data: TaskForUpdate) -> Result<()> {
+        Self::update_with_version(ctx, mm, id, None, data).await
+    }
+
+    /// Assigns the task to the user of the `assignee_id` (must exist), or unassigns it (`None`).
+    pub async fn assign(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        assignee_id: Option<i64>,
+    ) -> Result<()> {
+        if let Some(assignee_id) = assignee_id {
+            // Make sure the user exists.
+            UserBmc::get::<User>(ctx, mm, assignee_id).await?;
+        }
+
+        let fields = Fields::new(vec![Field::new(CommonIden::AssigneeId, assignee_id.into())]);
+        base::update_fields::<Self>(ctx, mm, id, fields).await
+    }
+
+    /// Same as `update`, but fails with `Error::VersionConflict` if the task `version`
+    /// is no longer the provided one.
+    pub async fn update_with_version(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        version: Option<i64>,
+        data: TaskForUpdate,
+    ) -> Result<()> {
+        if data.done != Some(true) {
+            return base::update_with_version::<Self, _>(ctx, mm, id, version, data).await;
+        }
+
+        let mm_txn = mm.new_with_txn();
+        base::update_with_version::<Self, _>(ctx, &mm_txn, id, version, data).await?;
+        Self::check_not_blocked(&mm_txn, &[id]).await?;
+        Self::create_next_occurrences(ctx, &mm_txn, &[id]).await?;
+        mm.commit_txn_of(&mm_txn).await
+    }
+
+    /// Applies the same update to all the tasks of the `ids`, and returns the updated ids.
+    /// Fails with `Error::EntityNotFound` if one of the tasks does not exist, or with
+    /// `Error::TaskBlocked` if one of them is still blocked once done, in which case none of
+    /// the tasks is updated.
+    pub async fn update_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        ids: &[i64],
+        data: TaskForUpdate,
+    ) -> Result<Vec<i64>> {
+        let done = data.done == Some(true);
+        let mm_txn = mm.new_with_txn();
+        let ids = base::update_many::<Self, _>(ctx, &mm_txn, ids, data).await?;
+        if done {
+            Self::check_not_blocked(&mm_txn, &ids).await?;
+            Self::create_next_occurrences(ctx, &mm_txn, &ids).await?;
+        }
+        mm.commit_txn_of(&mm_txn).await?;
+
+        Ok(ids)
+    }
+
+    /// Applies the same update to all the tasks matching the `filters`, and returns the
+    /// updated ids. (Same `Error::TaskBlocked` as `update_many`)
+    pub async fn update_many_by_filter(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Vec<TaskFilter>,
+        data: TaskForUpdate,
+    ) -> Result<Vec<i64>> {
+        let done = data.done == Some(true);
+        let mm_txn = mm.new_with_txn();
+        let ids = base::update_many_by_filter::<Self, _, _>(ctx, &mm_txn, filters, data).await?;
+        if done {
+            Self::check_not_blocked(&mm_txn, &ids).await?;
+            Self::create_next_occurrences(ctx, &mm_txn, &ids).await?;
+        }
+        mm.commit_txn_of(&mm_txn).await?;
+
+        Ok(ids)
+    }
+
+    /// Moves all the tasks matching the `filters` to the trash, and returns their number.
+    /// (Their subtasks are reparented, see `delete`)
+    pub async fn delete_many(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Vec<TaskFilter>,
+    ) -> Result<u64> {
+        let mm_txn = mm.new_with_txn();
+        let ids = base::delete_many::<Self, _>(ctx, &mm_txn, filters).await?;
+        Self::reparent_children(ctx, &mm_txn, &ids).await?;
+        mm.commit_txn_of(&mm_txn).await?;
+
+        Ok(ids.len() as u64)
+    }
+
+    /// Moves the task to the trash, see `restore` and `purge`.
+    /// Its subtasks are moved up to its parent (or become top-level tasks).
+    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let mm_txn = mm.new_with_txn();
+        base::delete::<Self>(ctx, &mm_txn, id).await?;
+        Self::reparent_children(ctx, &mm_txn, &[id]).await?;
+        mm.commit_txn_of(&mm_txn).await
+    }
+
+    pub async fn get_deleted(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
+        base::get_deleted::<Self, _>(ctx, mm, id).await
+    }
+
+    pub async fn list_deleted(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<TaskFilter>>,
+        list_options: Option<ListOptions>,
+    ) -> Result<Vec<Task>> {
+        base::list_deleted::<Self, _, _>(ctx, mm, filters, list_options).await
+    }
+
+    pub async fn list_deleted_page(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        filters: Option<Vec<TaskFilter>>,
+        list_options: Option<ListOptions>,
+        page_options: PageOptions,
+    ) -> Result<ListPage<Task>> {
+        base::list_deleted_page::<Self, _, _>(ctx, mm, filters, list_options, page_options).await
+    }
+
+    pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::restore::<Self>(ctx, mm, id).await
+    }
+
+    /// Permanently deletes a task that is in the trash.
+    pub async fn purge(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        base::purge::<Self>(ctx, mm, id).await
+    }
+
+    /// Moves all the tasks of the project to the trash, then permanently deletes them with
+    /// the ones already there (see `ProjectBmc::delete`).
+    pub(super) async fn purge_of_project(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: i64,
+    ) -> Result<()> {
+        let filters = || {
+            let mut filter = TaskFilter::default();
+            filter.fields.project_id = Some(project_id.into());
+            vec![filter]
+        };
+        Self::delete_many(ctx, mm, filters()).await?;
+        base::purge_many::<Self, _>(ctx, mm, filters()).await?;
+
+        Ok(())
+    }
+
+    /// Returns the changes of the task (oldest first), live or in the trash.
+    pub async fn get_history(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Vec<History>> {
+        // Make sure the task exists and is visible to the `Ctx` user.
+        match Self::get(ctx, mm, id).await {
+            Err(Error::EntityNotFound { .. }) => {
+                Self::get_deleted(ctx, mm, id).await?;
+            }
+            res => {
+                res?;
+            }
+        }
+
+        history::list_for_entity::<Self>(mm, id).await
+    }
+}
+
+// -- Hierarchy
+
+impl TaskBmc {
+    /// Moves the task under the `parent_id` task (of the same project), or to the top level
+    /// (`None`). Fails with `Error::TaskParentCycle` if the parent is the task itself or one of
+    /// its descendants.
+    pub async fn set_parent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        parent_id: Option<i64>,
+    ) -> Result<()> {
+        let task = Self::get(ctx, mm, id).await?;
+        if let Some(parent_id) = parent_id {
+            Self::check_parent(ctx, mm, task.project_id, parent_id).await?;
+            if Self::ancestor_ids(mm, parent_id).await?.contains(&id) {
+                return Err(Error::TaskParentCycle { id, parent_id });
+            }
+        }
+
+        let fields = Fields::new(vec![Field::new(TaskIden::ParentId, parent_id.into())]);
+        base::update_fields::<Self>(ctx, mm, id, fields).await
+    }
+
+    /// Returns the task with all its live descendants visible to the `Ctx` user (the subtree
+    /// of a hidden descendant is hidden as well), and the rollup of their `done`.
+    pub async fn get_tree(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<TaskTree> {
+        // Make sure the task is visible to the `Ctx` user.
+        let task = Self::get(ctx, mm, id).await?;
+
+        // -- Build the recursive query
+        //    WITH RECURSIVE tree (id) AS (
+        //      SELECT id FROM task WHERE parent_id = $1 AND deleted_at IS NULL [AND reader]
+        //      UNION
+        //      SELECT t.id FROM task t JOIN tree ON t.parent_id = tree.id
+        //      WHERE t.deleted_at IS NULL [AND reader]
+        //    )
+        //    SELECT ... FROM task WHERE id IN (SELECT id FROM tree) ORDER BY id
+        let mut cte_query = Query::select();
+        cte_query
+            .column(CommonIden::Id)
+            .from(Self::table_ref())
+            .and_where(Expr::col(TaskIden::ParentId).eq(id))
+            .and_where(Expr::col(CommonIden::DeletedAt).is_null())
+            .and_where_option(base::reader_cond::<Self>(ctx));
+        // NOTE: The `tree` CTE only has an `id` column, so the reader condition columns
+        //       resolve to the `t` ones.
+        cte_query.union(
+            UnionType::Distinct,
+            Query::select()
+                .column((SIden("t"), CommonIden::Id))
+                .from_as(Self::table_ref(), SIden("t"))
+                .inner_join(
+                    SIden("tree"),
+                    Expr::col((SIden("t"), TaskIden::ParentId))
+                        .equals((SIden("tree"), CommonIden::Id)),
+                )
+                .and_where(Expr::col((SIden("t"), CommonIden::DeletedAt)).is_null())
+                .and_where_option(base::reader_cond::<Self>(ctx))
+                .to_owned(),
+        );
+        let cte = CommonTableExpression::new()
+            .query(cte_query)
+            .column(CommonIden::Id)
+            .table_name(SIden("tree"))
+            .to_owned();
+
+        let mut select = Query::select();
+        select
+            .from(Self::table_ref())
+            .columns(Task::field_column_refs())
+            .and_where(
+                Expr::col(CommonIden::Id).in_subquery(
+                    Query::select()
+                        .column(CommonIden::Id)
+                        .from(SIden("tree"))
+                        .to_owned(),
+                ),
+            )
+            .order_by(CommonIden::Id, Order::Asc);
+        let query = select.with(WithClause::new().recursive(true).cte(cte).to_owned());
+
+        // -- Exec
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, Task, _>(&sql, values);
+        let descendants = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // -- Build the tree
+        let mut children: HashMap<i64, Vec<Task>> = HashMap::new();
+        for descendant in descendants {
+            if let Some(parent_id) = descendant.parent_id {
+                children.entry(parent_id).or_default().push(descendant);
+            }
+        }
+
+        Ok(TaskTree::build(task, &mut children))
+    }
+
+    /// Makes sure the `parent_id` task is visible to the `Ctx` user and in the `project_id`.
+    async fn check_parent(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        project_id: i64,
+        parent_id: i64,
+    ) -> Result<()> {
+        let parent = Self::get(ctx, mm, parent_id).await?;
+        if parent.project_id != project_id {
+            return Err(Error::TaskParentOtherProject {
+                parent_id,
+                project_id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the id of the task and the ids of all its ancestors (deleted or not).
+    async fn ancestor_ids(mm: &ModelManager, id: i64) -> Result<Vec<i64>> {
+        // NOTE: `UNION` (not `UNION ALL`) so that the recursion ends on a cycle.
+        let sql = "
+            WITH RECURSIVE ancestor (id, parent_id) AS (
+                SELECT id, parent_id FROM task WHERE id = $1
+                UNION
+                SELECT t.id, t.parent_id FROM task t JOIN ancestor a ON t.id = a.parent_id
+            )
+            SELECT id FROM ancestor";
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(sql).bind(id);
+        let ids = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Moves the live subtasks of the deleted tasks of the `ids` up to their closest live
+    /// ancestor (or to the top level), whoever owns them. (Recorded as `Update` changes)
+    async fn reparent_children(ctx: &Ctx, mm: &ModelManager, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // NOTE: `up` walks up from each child through its deleted ancestors, and the row
+        //       with the first live (or no) ancestor is the new parent.
+        let sql = "
+            WITH RECURSIVE up (child_id, ancestor_id) AS (
+                SELECT id, parent_id FROM task
+                WHERE parent_id = ANY($1) AND deleted_at IS NULL
+                UNION
+                SELECT up.child_id, a.parent_id FROM up JOIN task a ON a.id = up.ancestor_id
+                WHERE a.deleted_at IS NOT NULL
+            )
+            SELECT up.child_id, up.ancestor_id
+            FROM up LEFT JOIN task a ON a.id = up.ancestor_id
+            WHERE a.id IS NULL OR a.deleted_at IS NULL";
+        let sqlx_query = sqlx::query_as::<_, (i64, Option<i64>)>(sql).bind(ids);
+        let rows = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // -- Update the children of each new parent at once
+        let mut children_by_parent: BTreeMap<Option<i64>, Vec<i64>> = BTreeMap::new();
+        for (child_id, parent_id) in rows {
+            children_by_parent
+                .entry(parent_id)
+                .or_default()
+                .push(child_id);
+        }
+        for (parent_id, child_ids) in children_by_parent {
+            let fields = Fields::new(vec![Field::new(TaskIden::ParentId, parent_id.into())]);
+            base::update_fields_of_ids::<Self>(ctx, mm, &child_ids, fields).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// -- Dependencies
+
+impl TaskBmc {
+    /// Makes the `task_id` task blocked by the `blocker_id` task until the blocker is done
+    /// (no-op if already the case). Fails with `Error::TaskDependencyCycle` if the blocker is
+    /// the task itself or is (transitively) blocked by the task.
+    /// (Only by the owner of the task, see `check_owner`)
+    pub async fn add_dependency(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        task_id: i64,
+        blocker_id: i64,
+    ) -> Result<()> {
+        let mm_txn = mm.new_with_txn();
+        Self::check_owner(ctx, &mm_txn, task_id).await?;
+        // Make sure the blocker is visible to the `Ctx` user.
+        Self::get(ctx, &mm_txn, blocker_id).await?;
+
+        // NOTE: The cycle check and the insert are serialized with the other dependency adds,
+        //       so that two concurrent adds (e.g., A -> B and B -> A) cannot both pass it.
+        let sqlx_query = sqlx::query("SELECT pg_advisory_xact_lock(hashtext('task_dependency'))");
+        mm_txn.dbx().execute(sqlx_query).await?;
+
+        if task_id == blocker_id
+            || Self::blocker_ids(&mm_txn, blocker_id)
+                .await?
+                .contains(&task_id)
+        {
+            return Err(Error::TaskDependencyCycle {
+                task_id,
+                blocker_id,
+            });
+        }
+
+        let mut query = Query::insert();
+        query
+            .into_table(TaskDependencyIden::Table)
+            .columns([TaskDependencyIden::TaskId, TaskDependencyIden::BlockerId])
+            .values([task_id.into(), blocker_id.into()])?
+            .on_conflict(
+                OnConflict::columns([TaskDependencyIden::TaskId, TaskDependencyIden::BlockerId])
+                    .do_nothing()
+                    .to_owned(),
+            );
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm_txn.dbx().execute(sqlx_query).await?;
+
+        mm.commit_txn_of(&mm_txn).await
+    }
+
+    /// Removes the dependency of the `task_id` task on the `blocker_id` task (no-op if none).
+    /// (Only by the owner of the task, see `check_owner`)
+    pub async fn remove_dependency(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        task_id: i64,
+        blocker_id: i64,
+    ) -> Result<()> {
+        Self::check_owner(ctx, mm, task_id).await?;
+        // Make sure the blocker is visible to the `Ctx` user.
+        Self::get(ctx, mm, blocker_id).await?;
+
+        let mut query = Query::delete();
+        query
+            .from_table(TaskDependencyIden::Table)
+            .and_where(Expr::col(TaskDependencyIden::TaskId).eq(task_id))
+            .and_where(Expr::col(TaskDependencyIden::BlockerId).eq(blocker_id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
+
+        Ok(())
+    }
+
+    /// Fails with `Error::AccessDenied` if the `Ctx` user is not the owner of the visible
+    /// task (e.g., its assignee). (The root `Ctx` bypasses the check.)
+    pub(in crate::model) async fn check_owner(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+        let task = Self::get(ctx, mm, id).await?;
+        if !ctx.is_root() && task.owner_id != ctx.user_id() {
+            return Err(Error::AccessDenied {
+                entity: Self::TABLE,
+                id,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the ids of all the tasks blocking the task, directly or transitively.
+    async fn blocker_ids(mm: &ModelManager, task_id: i64) -> Result<Vec<i64>> {
+        // NOTE: `UNION` (not `UNION ALL`) so that the recursion ends on a cycle.
+        let sql = "
+            WITH RECURSIVE blocker (id) AS (
+                SELECT blocker_id FROM task_dependency WHERE task_id = $1
+                UNION
+                SELECT d.blocker_id FROM task_dependency d JOIN blocker b ON d.task_id = b.id
+            )
+            SELECT id FROM blocker";
+        let sqlx_query = sqlx::query_as::<_, (i64,)>(sql).bind(task_id);
+        let ids = mm.dbx().fetch_all(sqlx_query).await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Fails with `Error::TaskBlocked` (for the first one) if one of the tasks of the `ids`
+    /// is blocked.
+    async fn check_not_blocked(mm: &ModelManager, ids: &[i64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut query = Query::select();
+        query
+            .from(Self::table_ref())
+            .column(CommonIden::Id)
+            .and_where(Expr::col(CommonIden::Id).is_in(ids.iter().copied()))
+            .and_where(Expr::col(TaskIden::Blocked).eq(true))
+            .order_by(CommonIden::Id, Order::Asc)
+            .limit(1);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, (i64,), _>(&sql, values);
+        match mm.dbx().fetch_optional(sqlx_query).await? {
+            Some((id,)) => Err(Error::TaskBlocked { id }),
+            None => Ok(()),
+        }
+    }
+}
+
+// -- Recurrence
+
+impl TaskBmc {
+    /// Sets the recurrence rule of the task (see `recurrence`), or removes it (`None`).
+    pub async fn set_recurrence(
+        ctx: &Ctx,
+        mm: &ModelManager,
+        id: i64,
+        recurrence: Option<String>,
+    ) -> Result<()> {
+        let mut recurrence = recurrence;
+        normalize_recurrence(&mut recurrence)?;
+
+        let fields = Fields::new(vec![Field::new(TaskIden::Recurrence, recurrence.into())]);
+        base::update_fields::<Self>(ctx, mm, id, fields).await
+    }
+
+    /// Creates the next occurrence of the done recurring tasks of the `ids` which do not have
+    /// one yet, due one recurrence after their due date (or after today).
+    /// The occurrence is a copy of the task (with its assignee and labels), not done.
+    async fn create_next_occurrences(ctx: &Ctx, mm: &ModelManager, ids: &[i64]) -> Result<()> {
+        // -- Select (and lock) the tasks to spawn from
+        let mut query = Query::select();
+        query
+            .from(Self::table_ref())
+            .columns(Task::field_column_refs())
+            .and_where(Expr::col(CommonIden::Id).is_in(ids.iter().copied()))
+            .and_where(Expr::col(TaskIden::Done).eq(true))
+            .and_where(Expr::col(TaskIden::Recurrence).is_not_null())
+            .and_where(Expr::col(TaskIden::NextOccurrenceId).is_null())
+            .and_where(Expr::col(CommonIden::DeletedAt).is_null())
+            .order_by(CommonIden::Id, Order::Asc)
+            .lock_exclusive();
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let sqlx_query = sqlx::query_as_with::<_, Task, _>(&sql, values);
+        let tasks = mm.dbx().fetch_all(sqlx_query).await?;
+
+        // -- Create the next occurrences
+        for task in tasks {
+            let Some(rule) = task.recurrence.as_deref() else {
+                continue;
+            };
+            let recurrence: Recurrence = rule.parse()?;
+            let from = task.due_date.unwrap_or_else(|| now_utc().date());
+            let Some(due_date) = recurrence.next_date(from) else {
+                continue;
+            };
+
+            let task_c = TaskForCreate {
+                title: task.title,
+                project_id: task.project_id,
+                parent_id: task.parent_id,
+                description: task.description,
+                due_date: Some(due_date),
+                priority: Some(task.priority),
+                recurrence: task.recurrence,
+            };
+            // The occurrence has the same owner (even when done by the root `Ctx`) and assignee.
+            let mut fields = task_c.not_none_fields();
+            fields.push(Field::new(TaskIden::OwnerId, task.owner_id.into()));
+            fields.push(Field::new(CommonIden::AssigneeId, task.assignee_id.into()));
+            let next_id = base::create_fields::<Self>(ctx, mm, fields).await?;
+            copy_task_labels(mm, task.id, next_id).await?;
+
+            // NOTE: The link to the occurrence is internal bookkeeping of the done update (not
+            //       a change of the task), so it is neither versioned nor recorded.
+            let mut query = Query::update();
+            query
+                .table(Self::table_ref())
+                .value(TaskIden::NextOccurrenceId, next_id)
+                .and_where(Expr::col(CommonIden::Id).eq(task.id));
+            let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+            let sqlx_query = sqlx::query_with(&sql, values);
+            mm.dbx().execute(sqlx_query).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates and normalizes the `recurrence` rule (`None` stays `None`).
+fn normalize_recurrence(recurrence: &mut Option<String>) -> Result<()> {
+    if let Some(rule) = recurrence.as_mut() {
+        *rule = rule.parse::<Recurrence>()?.to_string();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(unused)]
+    use crate::_dev_utils::{self, seed_project, seed_tasks};
+
+    use super::*;
+    use crate::model::label::{LabelBmc, LabelForCreate};
+    use crate::model::Error;
+    use anyhow::{Context, Result};
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_title = "test_create_ok title";
+        let fx_project_id = seed_project(&ctx, &mm, "test_create_ok project").await?;
+
+        let task_c = TaskForCreate {
+            title: fx_title.to_string(),
+            project_id: fx_project_id,
+            ..Default::default()
+        };
+        let id = TaskBmc::create(&ctx, &mm, task_c).await?;
+
+        let task = TaskBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(task.title, fx_title);
+        assert_eq!(task.project_id, fx_project_id);
+        assert_eq!(task.cid, ctx.user_id());
+        assert_eq!(task.ctime, task.mtime);
+
+        TaskBmc::delete(&ctx, &mm, id).await?;
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_error_not_found() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_id = 100;
+
+        let res = TaskBmc::get(&ctx, &mm, fx_id).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "task",
+                    id: 100
+                })
+            ),
+            "EntityNotFound not matching"
+        );
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_title = "test_update_ok - task 01";
+        let fx_title_new = "test_update_ok - task 01 - new";
+        let fx_project_id = seed_project(&ctx, &mm, "test_update_ok project").await?;
+        let fx_task = _dev_utils::seed_tasks(&ctx, &mm, fx_project_id, &[fx_title])
+            .await?
+            .remove(0);
+
+        TaskBmc::update(
+            &ctx,
+            &mm,
+            fx_task.id,
+            TaskForUpdate {
+                title: Some(fx_title_new.to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+        assert_eq!(task.title, fx_title_new);
+        assert_eq!(task.ctime, fx_task.ctime);
+        assert!(task.mtime > fx_task.mtime, "mtime should be updated");
+
+        TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_version_conflict_err() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id =
+            seed_project(&ctx, &mm, "test_update_version_conflict_err project").await?;
+        let fx_task = seed_tasks(
+            &ctx,
+            &mm,
+            fx_project_id,
+            &["test_update_version_conflict_err - task 01"],
+        )
+        .await?
+        .remove(0);
+        let fx_version = fx_task.version;
+
+        // -- Exec: first writer, with the read version.
+        TaskBmc::update_with_version(
+            &ctx,
+            &mm,
+            fx_task.id,
+            Some(fx_version),
+            TaskForUpdate {
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+        // -- Exec: second writer, with the now stale version.
+        let res = TaskBmc::update_with_version(
+            &ctx,
+            &mm,
+            fx_task.id,
+            Some(fx_version),
+            TaskForUpdate {
+                title: Some("stale title".to_string()),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(Error::VersionConflict { entity: "task", id, expected })
+                    if id == fx_task.id && expected == fx_version
+            ),
+            "VersionConflict not matching"
+        );
+        let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+        assert_eq!(task.version, fx_version + 1);
+        assert!(task.done);
+        assert_eq!(task.title, fx_task.title);
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_txn_commit_rollback_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_txn_commit_rollback_ok project").await?;
+
+        // -- Exec: rolled back txn.
+        let mm_txn = mm.new_with_txn();
+        let rolled_back_id = TaskBmc::create(
+            &ctx,
+            &mm_txn,
+            TaskForCreate {
+                title: "test_txn_commit_rollback_ok rolled back".to_string(),
+                project_id: fx_project_id,
+                ..Default::default()
+            },
+        )
+        .await?;
+        // Visible inside the transaction.
+        TaskBmc::get(&ctx, &mm_txn, rolled_back_id).await?;
+        mm_txn.rollback_txn().await?;
+
+        // -- Exec: committed txn.
+        let mm_txn = mm.new_with_txn();
+        let committed_id = TaskBmc::create(
+            &ctx,
+            &mm_txn,
+            TaskForCreate {
+                title: "test_txn_commit_rollback_ok committed".to_string(),
+                project_id: fx_project_id,
+                ..Default::default()
+            },
+        )
+        .await?;
+        mm_txn.commit_txn().await?;
+
+        // -- Check
+        let res = TaskBmc::get(&ctx, &mm, rolled_back_id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "Rolled back task should not exist"
+        );
+        let task = TaskBmc::get(&ctx, &mm, committed_id).await?;
+        assert_eq!(task.title, "test_txn_commit_rollback_ok committed");
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_all_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &["test_list_all_ok-task 01", "test_list_all_ok-task 02"];
+        let fx_project_id = seed_project(&ctx, &mm, "test_list_all_ok project").await?;
+        seed_tasks(&ctx, &mm, fx_project_id, fx_titles).await?;
+
+        let tasks = TaskBmc::list(&ctx, &mm, None, None).await?;
+
+        let tasks: Vec<_> = tasks
+            .into_iter()
+            .filter(|t| t.title.starts_with("test_list_all_ok-task"))
+            .collect();
+        assert!(tasks.len() == 2, "number of seeded tasks.");
+
+        for task in tasks.iter() {
+            TaskBmc::delete(&ctx, &mm, task.id).await?;
+        }
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_by_filter_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &[
+            "test_list_by_filter_ok-task 01.a",
+            "test_list_by_filter_ok-task 01.b",
+            "test_list_by_filter_ok-task 02.a",
+            "test_list_by_filter_ok-task 02.b",
+            "test_list_by_filter_ok-task 03",
+        ];
+        let fx_project_id = seed_project(&ctx, &mm, "test_list_by_filter_ok project").await?;
+        seed_tasks(&ctx, &mm, fx_project_id, fx_titles).await?;
+
+        let filters: Vec<TaskFilter> = serde_json::from_value(json!([
+        {
+            "title": {
+                "$endsWithAny": [".a", ".b"],
+            }
+        },
+        {
+            "title": {
+                "$contains": "03"
+            }
+        }
+        ]))?;
+        let len = 3;
+        let list_options: ListOptions = serde_json::from_value(json!({
+            "limit": len,
+            "order_bys": "!id",
+        }))?;
+        let tasks = TaskBmc::list(&ctx, &mm, Some(filters), Some(list_options)).await?;
+        assert!(tasks.len() == len, "number of seeded tasks.");
+
+        // Cleanup
+        let tasks = TaskBmc::list(
+            &ctx,
+            &mm,
+            Some(serde_json::from_value(
+                json!([{"title": {"$startsWith": "test_list_by_filter_ok"}}]),
+            )?),
+            None,
+        )
+        .await?;
+        assert_eq!(tasks.len(), 5);
+        for task in tasks {
+            TaskBmc::delete(&ctx, &mm, task.id).await?;
+        }
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_page_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &[
+            "test_list_page_ok-task 01",
+            "test_list_page_ok-task 02",
+            "test_list_page_ok-task 03",
+        ];
+        let fx_project_id = seed_project(&ctx, &mm, "test_list_page_ok project").await?;
+        seed_tasks(&ctx, &mm, fx_project_id, fx_titles).await?;
+        let fx_filters = || -> Result<Vec<TaskFilter>> {
+            Ok(serde_json::from_value(
+                json!([{"project_id": fx_project_id}]),
+            )?)
+        };
+
+        // -- Exec & Check: first page, with total.
+        let list_options: ListOptions =
+            serde_json::from_value(json!({"limit": 2, "order_bys": "id"}))?;
+        let page_options = PageOptions {
+            with_total: true,
+            ..Default::default()
+        };
+        let page = TaskBmc::list_page(
+            &ctx,
+            &mm,
+            Some(fx_filters()?),
+            Some(list_options),
+            page_options,
+        )
+        .await?;
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(page.items[0].title, fx_titles[0]);
+        assert_eq!(page.total, Some(3));
+        assert_eq!((page.limit, page.offset), (2, 0));
+        assert!(page.has_more);
+
+        // -- Exec & Check: last page, without total.
+        let list_options: ListOptions =
+            serde_json::from_value(json!({"limit": 2, "offset": 2, "order_bys": "id"}))?;
+        let page = TaskBmc::list_page(
+            &ctx,
+            &mm,
+            Some(fx_filters()?),
+            Some(list_options),
+            PageOptions::default(),
+        )
+        .await?;
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].title, fx_titles[2]);
+        assert_eq!(page.total, None);
+        assert!(!page.has_more);
+        assert_eq!(page.next_cursor, None);
+
+        // -- Check: count
+        assert_eq!(TaskBmc::count(&ctx, &mm, Some(fx_filters()?)).await?, 3);
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_page_cursor_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &[
+            "test_list_page_cursor_ok-task 01",
+            "test_list_page_cursor_ok-task 02",
+            "test_list_page_cursor_ok-task 03",
+            "test_list_page_cursor_ok-task 04",
+            "test_list_page_cursor_ok-task 05",
+        ];
+        let fx_project_id = seed_project(&ctx, &mm, "test_list_page_cursor_ok project").await?;
+        seed_tasks(&ctx, &mm, fx_project_id, fx_titles).await?;
+        let fx_filters = || -> Result<Vec<TaskFilter>> {
+            Ok(serde_json::from_value(
+                json!([{"project_id": fx_project_id}]),
+            )?)
+        };
+        let fx_list_options = || -> Result<ListOptions> {
+            Ok(serde_json::from_value(
+                json!({"limit": 2, "order_bys": "!title"}),
+            )?)
+        };
+
+        // -- Exec: walk the pages with the cursors.
+        let mut titles = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page_options = PageOptions {
+                cursor: cursor.take(),
+                ..Default::default()
+            };
+            let page = TaskBmc::list_page(
+                &ctx,
+                &mm,
+                Some(fx_filters()?),
+                Some(fx_list_options()?),
+                page_options,
+            )
+            .await?;
+            titles.extend(page.items.into_iter().map(|t| t.title));
+            assert_eq!(page.has_more, page.next_cursor.is_some());
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor),
+                None => break,
+            }
+        }
+
+        // -- Check
+        let mut fx_titles_desc: Vec<String> = fx_titles.iter().map(|t| t.to_string()).collect();
+        fx_titles_desc.reverse();
+        assert_eq!(titles, fx_titles_desc);
+
+        // -- Check: a cursor with another sort is invalid.
+        let page = TaskBmc::list_page(
+            &ctx,
+            &mm,
+            Some(fx_filters()?),
+            Some(fx_list_options()?),
+            PageOptions::default(),
+        )
+        .await?;
+        let list_options: ListOptions =
+            serde_json::from_value(json!({"limit": 2, "order_bys": "title"}))?;
+        let page_options = PageOptions {
+            cursor: page.next_cursor,
+            ..Default::default()
+        };
+        let res = TaskBmc::list_page(
+            &ctx,
+            &mm,
+            Some(fx_filters()?),
+            Some(list_options),
+            page_options,
+        )
+        .await;
+        assert!(
+            matches!(res, Err(Error::ListCursorInvalid)),
+            "ListCursorInvalid not matching"
+        );
+
+        // -- Check: a sort on an unknown column is invalid.
+        let list_options: ListOptions =
+            serde_json::from_value(json!({"order_bys": "no_such_column"}))?;
+        for res in [
+            TaskBmc::list(&ctx, &mm, Some(fx_filters()?), Some(list_options.clone()))
+                .await
+                .map(|_| ()),
+            TaskBmc::list_page(
+                &ctx,
+                &mm,
+                Some(fx_filters()?),
+                Some(list_options),
+                PageOptions::default(),
+            )
+            .await
+            .map(|_| ()),
+        ] {
+            assert!(
+                matches!(&res, Err(Error::ListOrderByInvalid(column)) if column == "no_such_column"),
+                "ListOrderByInvalid not matching"
+            );
+        }
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_page_cursor_nullable_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_titles = &[
+            "test_list_page_cursor_nullable_ok-task 01",
+            "test_list_page_cursor_nullable_ok-task 02",
+            "test_list_page_cursor_nullable_ok-task 03",
+            "test_list_page_cursor_nullable_ok-task 04",
+            "test_list_page_cursor_nullable_ok-task 05",
+        ];
+        let fx_project_id =
+            seed_project(&ctx, &mm, "test_list_page_cursor_nullable_ok project").await?;
+        let fx_tasks = seed_tasks(&ctx, &mm, fx_project_id, fx_titles).await?;
+        let fx_ids: Vec<i64> = fx_tasks.iter().map(|t| t.id).collect();
+        for (idx, due_date) in [
+            (1, time::macros::date!(2024 - 03 - 02)),
+            (3, time::macros::date!(2024 - 03 - 01)),
+        ] {
+            let task_u = TaskForUpdate {
+                due_date: Some(Clearable(Some(due_date))),
+                ..Default::default()
+            };
+            TaskBmc::update(&ctx, &mm, fx_ids[idx], task_u).await?;
+        }
+        let fx_filters = || -> Result<Vec<TaskFilter>> {
+            Ok(serde_json::from_value(
+                json!([{"project_id": fx_project_id}]),
+            )?)
+        };
+
+        // -- Exec & Check: walk the pages of both orders, the NULLs last.
+        for (order_bys, fx_order) in [
+            ("due_date", [3, 1, 0, 2, 4]),
+            ("!due_date", [1, 3, 0, 2, 4]),
+        ] {
+            let mut ids = Vec::new();
+            let mut cursor = None;
+            loop {
+                let list_options: ListOptions =
+                    serde_json::from_value(json!({"limit": 2, "order_bys": order_bys}))?;
+                let page_options = PageOptions {
+                    cursor: cursor.take(),
+                    ..Default::default()
+                };
+                let page = TaskBmc::list_page(
+                    &ctx,
+                    &mm,
+                    Some(fx_filters()?),
+                    Some(list_options),
+                    page_options,
+                )
+                .await?;
+                ids.extend(page.items.into_iter().map(|t| t.id));
+                match page.next_cursor {
+                    Some(next_cursor) => cursor = Some(next_cursor),
+                    None => break,
+                }
+            }
+            let fx_order_ids: Vec<i64> = fx_order.iter().map(|idx| fx_ids[*idx]).collect();
+            assert_eq!(ids, fx_order_ids, "order_bys: {order_bys}");
+        }
+
+        // -- Check: a limit under 1 is invalid.
+        let list_options: ListOptions = serde_json::from_value(json!({"limit": 0}))?;
+        let res = TaskBmc::list_page(
+            &ctx,
+            &mm,
+            Some(fx_filters()?),
+            Some(list_options),
+            PageOptions::default(),
+        )
+        .await;
+        assert!(
+            matches!(res, Err(Error::ListLimitUnderMin { .. })),
+            "ListLimitUnderMin not matching"
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_update_delete_many_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_create_update_delete_many_ok").await?;
+        let fx_titles = [
+            "test_create_update_delete_many_ok-task 01",
+            "test_create_update_delete_many_ok-task 02",
+            "test_create_update_delete_many_ok-task 03",
+        ];
+        let fx_filters = || -> Result<Vec<TaskFilter>> {
+            Ok(serde_json::from_value(
+                json!([{"project_id": fx_project_id}]),
+            )?)
+        };
+
+        // -- Exec & Check: create_many
+        let tasks_c = fx_titles
+            .iter()
+            .map(|title| TaskForCreate {
+                title: title.to_string(),
+                project_id: fx_project_id,
+                ..Default::default()
+            })
+            .collect();
+        let ids = TaskBmc::create_many(&ctx, &mm, tasks_c).await?;
+        let tasks = TaskBmc::list_by_ids(&ctx, &mm, &ids).await?;
+        let titles: Vec<&str> = tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, fx_titles);
+
+        // -- Exec & Check: update_many
+        let data = TaskForUpdate {
+            done: Some(true),
+            ..Default::default()
+        };
+        let updated_ids = TaskBmc::update_many(&ctx, &mm, &ids[..2], data).await?;
+        assert_eq!(updated_ids.len(), 2);
+        let tasks = TaskBmc::list_by_ids(&ctx, &mm, &ids).await?;
+        let dones: Vec<bool> = tasks.iter().map(|t| t.done).collect();
+        assert_eq!(dones, &[true, true, false]);
+        assert_eq!(tasks[0].version, 1);
+
+        // -- Exec & Check: update_many_by_filter
+        let data = TaskForUpdate {
+            done: Some(false),
+            ..Default::default()
+        };
+        let updated_ids = TaskBmc::update_many_by_filter(&ctx, &mm, fx_filters()?, data).await?;
+        assert_eq!(updated_ids.len(), 3);
+
+        // -- Check: empty filters are rejected, and nothing is deleted.
+        for fx_empty_filters in [
+            json!([]),
+            json!([{}]),
+            json!([{"project_id": fx_project_id}, {}]),
+        ] {
+            let filters: Vec<TaskFilter> = serde_json::from_value(fx_empty_filters)?;
+            let res = TaskBmc::delete_many(&ctx, &mm, filters).await;
+            assert!(
+                matches!(res, Err(Error::BulkFilterEmpty)),
+                "BulkFilterEmpty not matching"
+            );
+        }
+        let res =
+            TaskBmc::update_many_by_filter(&ctx, &mm, Vec::new(), TaskForUpdate::default()).await;
+        assert!(
+            matches!(res, Err(Error::BulkFilterEmpty)),
+            "BulkFilterEmpty not matching"
+        );
+        assert_eq!(TaskBmc::count(&ctx, &mm, Some(fx_filters()?)).await?, 3);
+
+        // -- Exec & Check: delete_many
+        let count = TaskBmc::delete_many(&ctx, &mm, fx_filters()?).await?;
+        assert_eq!(count, 3);
+        assert_eq!(TaskBmc::count(&ctx, &mm, Some(fx_filters()?)).await?, 0);
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_update_many_err_not_found() -> Result<()> {
+        let mm = _dev_utils::init_test().await.new_with_txn();
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_update_many_err_not_found").await?;
+        let fx_tasks = seed_tasks(&ctx, &mm, fx_project_id, &["task 01"]).await?;
+        let fx_missing_id = 100;
+
+        // -- Exec
+        let data = TaskForUpdate {
+            done: Some(true),
+            ..Default::default()
+        };
+        let res = TaskBmc::update_many(&ctx, &mm, &[fx_tasks[0].id, fx_missing_id], data).await;
+
+        // -- Check
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "task",
+                    id: 100
+                })
+            ),
+            "EntityNotFound not matching"
+        );
+
+        // -- Clean
+        mm.rollback_txn().await?;
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_upsert_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_upsert_ok project").await?;
+        let fx_task_u = |title: &str| TaskForUpsert {
+            external_ref: "test_upsert_ok-EXT-1".to_string(),
+            project_id: fx_project_id,
+            title: title.to_string(),
+            done: None,
+        };
+
+        // -- Exec & Check: insert
+        let upserted = TaskBmc::upsert(&ctx, &mm, fx_task_u("title 01")).await?;
+        assert!(upserted.inserted);
+        let task = TaskBmc::get(&ctx, &mm, upserted.id).await?;
+        assert_eq!(task.external_ref.as_deref(), Some("test_upsert_ok-EXT-1"));
+        assert_eq!((task.title.as_str(), task.version), ("title 01", 0));
+
+        // -- Exec & Check: update (of the trashed task)
+        TaskBmc::delete(&ctx, &mm, upserted.id).await?;
+        let updated = TaskBmc::upsert(&ctx, &mm, fx_task_u("title 02")).await?;
+        assert!(!updated.inserted);
+        assert_eq!(updated.id, upserted.id);
+        let task = TaskBmc::get(&ctx, &mm, updated.id).await?;
+        assert_eq!(task.title, "title 02");
+        assert_eq!(task.deleted_at, None);
+        let history = TaskBmc::get_history(&ctx, &mm, updated.id).await?;
+        let upsert_change = history.last().context("Should have the upsert history")?;
+        assert_eq!(upsert_change.op, "update");
+        assert_eq!(upsert_change.before["title"], "title 01");
+        assert_eq!(upsert_change.after["title"], "title 02");
+
+        // -- Check: done on a blocked task
+        let fx_blocker_id = TaskBmc::create(
+            &ctx,
+            &mm,
+            TaskForCreate {
+                title: "test_upsert_ok blocker".to_string(),
+                project_id: fx_project_id,
+                ..Default::default()
+            },
+        )
+        .await?;
+        TaskBmc::add_dependency(&ctx, &mm, updated.id, fx_blocker_id).await?;
+        let task_u = TaskForUpsert {
+            done: Some(true),
+            ..fx_task_u("title 03")
+        };
+        let res = TaskBmc::upsert(&ctx, &mm, task_u).await;
+        assert!(
+            matches!(res, Err(Error::TaskBlocked { id }) if id == updated.id),
+            "upsert should fail with TaskBlocked"
+        );
+        let task = TaskBmc::get(&ctx, &mm, updated.id).await?;
+        assert_eq!((task.title.as_str(), task.done), ("title 02", false));
+
+        // -- Check: done on a recurring task creates its next occurrence
+        TaskBmc::remove_dependency(&ctx, &mm, updated.id, fx_blocker_id).await?;
+        TaskBmc::set_recurrence(&ctx, &mm, updated.id, Some("FREQ=DAILY".to_string())).await?;
+        let task_u = TaskForUpsert {
+            done: Some(true),
+            ..fx_task_u("title 04")
+        };
+        TaskBmc::upsert(&ctx, &mm, task_u).await?;
+        let task = TaskBmc::get(&ctx, &mm, updated.id).await?;
+        let next_id = task
+            .next_occurrence_id
+            .context("Should have a next occurrence")?;
+        let next = TaskBmc::get(&ctx, &mm, next_id).await?;
+        assert_eq!((next.title.as_str(), next.done), ("title 04", false));
 
-    pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
-        base::delete::<Self>(ctx, mm, id).await
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    #![allow(unused)]
-    use crate::_dev_utils::{self, seed_tasks};
+    #[serial]
+    #[tokio::test]
+    async fn test_search_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_search_ok project").await?;
+        let fx_other_project_id = seed_project(&ctx, &mm, "test_search_ok other").await?;
+        seed_tasks(
+            &ctx,
+            &mm,
+            fx_project_id,
+            &[
+                "Fixing the login page",
+                "Login fixes for the mobile app",
+                "Write the release notes",
+                "Login <img src=x onerror=alert(1)> & more",
+            ],
+        )
+        .await?;
+        seed_tasks(&ctx, &mm, fx_other_project_id, &["Fix login"]).await?;
+        let filters: Vec<TaskFilter> =
+            serde_json::from_value(json!([{"project_id": fx_project_id}]))?;
 
-    use super::*;
-    use crate::model::Error;
-    use anyhow::Result;
-    use serde_json::json;
-    use serial_test::serial;
+        // -- Exec
+        let hits = TaskBmc::search(&ctx, &mm, "fix login -mobile", Some(filters), None).await?;
+
+        // -- Check
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entity.title, "Fixing the login page");
+        assert_eq!(hits[0].snippet, "<b>Fixing</b> the <b>login</b> page");
+        assert!(hits[0].rank > 0.);
+
+        // -- Check: the user text of the snippets is HTML escaped.
+        let filters: Vec<TaskFilter> =
+            serde_json::from_value(json!([{"project_id": fx_project_id}]))?;
+        let hits = TaskBmc::search(&ctx, &mm, "onerror", Some(filters), None).await?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].snippet,
+            "Login &lt;img src=x <b>onerror</b>=alert(1)&gt; &amp; more"
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        ProjectBmc::delete(&ctx, &mm, fx_other_project_id).await?;
+        Ok(())
+    }
 
     #[serial]
     #[tokio::test]
-    async fn test_create_ok() -> Result<()> {
+    async fn test_details_ok() -> Result<()> {
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_title = "test_create_ok title";
+        let fx_project_id = seed_project(&ctx, &mm, "test_details_ok project").await?;
+        let fx_due_date = time::macros::date!(2024 - 03 - 15);
 
+        // -- Exec: create with details
         let task_c = TaskForCreate {
-            title: fx_title.to_string(),
+            title: "test_details_ok task".to_string(),
+            project_id: fx_project_id,
+            description: Some("The long description".to_string()),
+            due_date: Some(fx_due_date),
+            priority: Some(TaskPriority::High),
+            ..Default::default()
         };
         let id = TaskBmc::create(&ctx, &mm, task_c).await?;
 
+        // -- Check: details
         let task = TaskBmc::get(&ctx, &mm, id).await?;
-        assert_eq!(task.title, fx_title);
+        assert_eq!(task.description.as_deref(), Some("The long description"));
+        assert_eq!(task.due_date, Some(fx_due_date));
+        assert_eq!(task.priority, TaskPriority::High);
+        assert_eq!(task.done_at, None);
 
-        TaskBmc::delete(&ctx, &mm, id).await?;
+        // -- Check: filters on the due date range and priority
+        let filters: Vec<TaskFilter> = serde_json::from_value(json!([{
+            "project_id": fx_project_id,
+            "due_date": {"$gte": "2024-03-01", "$lt": "2024-04-01"},
+            "priority": "High"
+        }]))?;
+        let tasks = TaskBmc::list(&ctx, &mm, Some(filters), None).await?;
+        assert_eq!(tasks.len(), 1);
+
+        // -- Exec & Check: `done_at` follows `done`
+        let data = TaskForUpdate {
+            done: Some(true),
+            ..Default::default()
+        };
+        TaskBmc::update(&ctx, &mm, id, data).await?;
+        let task = TaskBmc::get(&ctx, &mm, id).await?;
+        assert!(task.done_at.is_some());
+
+        let data = TaskForUpdate {
+            done: Some(false),
+            priority: Some(TaskPriority::Low),
+            ..Default::default()
+        };
+        TaskBmc::update(&ctx, &mm, id, data).await?;
+        let task = TaskBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(task.done_at, None);
+        assert_eq!(task.priority, TaskPriority::Low);
+
+        // -- Exec & Check: missing fields are unchanged, `null` ones are cleared.
+        let data: TaskForUpdate = serde_json::from_value(json!({"title": "renamed"}))?;
+        TaskBmc::update(&ctx, &mm, id, data).await?;
+        let task = TaskBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(task.description.as_deref(), Some("The long description"));
+        assert_eq!(task.due_date, Some(fx_due_date));
+
+        let data: TaskForUpdate =
+            serde_json::from_value(json!({"description": null, "due_date": null}))?;
+        TaskBmc::update(&ctx, &mm, id, data).await?;
+        let task = TaskBmc::get(&ctx, &mm, id).await?;
+        assert_eq!(task.title, "renamed");
+        assert_eq!(task.description, None);
+        assert_eq!(task.due_date, None);
 
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_get_error_not_found() -> Result<()> {
+    async fn test_delete_error_not_found() -> Result<()> {
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
         let fx_id = 100;
 
-        let res = TaskBmc::get(&ctx, &mm, fx_id).await;
+        let res = TaskBmc::delete(&ctx, &mm, fx_id).await;
         assert!(
             matches!(
                 res,
@@ -116,131 +1867,502 @@ mod tests {
             ),
             "EntityNotFound not matching"
         );
+
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_update_ok() -> Result<()> {
+    async fn test_other_owner_error_not_found() -> Result<()> {
         let mm = _dev_utils::init_test().await;
-        let ctx = Ctx::root_ctx();
-        let fx_title = "test_update_ok - task 01";
-        let fx_title_new = "test_update_ok - task 01 - new";
-        let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &[fx_title])
+        let root_ctx = Ctx::root_ctx();
+        let owner_ctx = Ctx::new(1000)?;
+        let other_ctx = Ctx::new(1001)?;
+        let fx_title = "test_other_owner_error_not_found task 01";
+        let fx_project_id = seed_project(&owner_ctx, &mm, "test_other_owner project").await?;
+        let fx_task = seed_tasks(&owner_ctx, &mm, fx_project_id, &[fx_title])
             .await?
             .remove(0);
+        assert_eq!(fx_task.owner_id, owner_ctx.user_id());
 
-        TaskBmc::update(
-            &ctx,
+        // Get
+        let res = TaskBmc::get(&other_ctx, &mm, fx_task.id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "get should not find other user's task"
+        );
+        // List
+        let tasks = TaskBmc::list(&other_ctx, &mm, None, None).await?;
+        assert!(tasks.iter().all(|t| t.id != fx_task.id));
+        // Update
+        let res = TaskBmc::update(
+            &other_ctx,
             &mm,
             fx_task.id,
             TaskForUpdate {
-                title: Some(fx_title_new.to_string()),
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "update should not find other user's task"
+        );
+        // Delete
+        let res = TaskBmc::delete(&other_ctx, &mm, fx_task.id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "delete should not find other user's task"
+        );
+        // Create in other user's project
+        let res = TaskBmc::create(
+            &other_ctx,
+            &mm,
+            TaskForCreate {
+                title: fx_title.to_string(),
+                project_id: fx_project_id,
                 ..Default::default()
             },
         )
+        .await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "project",
+                    ..
+                })
+            ),
+            "create should not find other user's project"
+        );
+
+        // The root ctx bypasses ownership.
+        let task = TaskBmc::get(&root_ctx, &mm, fx_task.id).await?;
+        assert!(!task.done);
+
+        ProjectBmc::delete(&root_ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_assign_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let root_ctx = Ctx::root_ctx();
+        let assignee_ctx = Ctx::new(1000)?;
+        let fx_project_id = seed_project(&root_ctx, &mm, "test_assign_ok project").await?;
+        let fx_tasks = seed_tasks(
+            &root_ctx,
+            &mm,
+            fx_project_id,
+            &["test_assign_ok task 01", "test_assign_ok task 02"],
+        )
         .await?;
+        let fx_id = fx_tasks[0].id;
 
-        let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
-        assert_eq!(task.title, fx_title_new);
+        // -- Exec
+        TaskBmc::assign(&root_ctx, &mm, fx_id, Some(assignee_ctx.user_id())).await?;
+        TaskBmc::assign(&root_ctx, &mm, fx_tasks[1].id, Some(assignee_ctx.user_id())).await?;
+        TaskBmc::assign(&root_ctx, &mm, fx_tasks[1].id, None).await?;
+
+        // -- Check: the assignee can read the task
+        let task = TaskBmc::get(&assignee_ctx, &mm, fx_id).await?;
+        assert_eq!(task.assignee_id, Some(assignee_ctx.user_id()));
+        let page =
+            TaskBmc::list_assigned_page(&assignee_ctx, &mm, None, None, PageOptions::default())
+                .await?;
+        let ids: Vec<i64> = page.items.iter().map(|t| t.id).collect();
+        assert_eq!(ids, &[fx_id]);
+
+        // -- Check: but cannot modify it
+        let res = TaskBmc::update(
+            &assignee_ctx,
+            &mm,
+            fx_id,
+            TaskForUpdate {
+                done: Some(true),
+                ..Default::default()
+            },
+        )
+        .await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "the assignee should not update the task"
+        );
+
+        // -- Check: unknown user
+        let res = TaskBmc::assign(&root_ctx, &mm, fx_id, Some(100)).await;
+        assert!(
+            matches!(
+                res,
+                Err(Error::EntityNotFound {
+                    entity: "user",
+                    id: 100
+                })
+            ),
+            "EntityNotFound not matching"
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&root_ctx, &mm, fx_project_id).await?;
 
-        TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_list_all_ok() -> Result<()> {
+    async fn test_tree_ok() -> Result<()> {
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_titles = &["test_list_all_ok-task 01", "test_list_all_ok-task 02"];
-        seed_tasks(&ctx, &mm, fx_titles).await?;
+        let fx_project_id = seed_project(&ctx, &mm, "test_tree_ok project").await?;
+        let fx_other_project_id = seed_project(&ctx, &mm, "test_tree_ok other project").await?;
+        let create = |title: &str, parent_id: Option<i64>, project_id: i64| {
+            let task_c = TaskForCreate {
+                title: title.to_string(),
+                project_id,
+                parent_id,
+                ..Default::default()
+            };
+            TaskBmc::create(&ctx, &mm, task_c)
+        };
 
-        let tasks = TaskBmc::list(&ctx, &mm, None, None).await?;
+        // -- Exec: a > (b > c, d)
+        let a = create("a", None, fx_project_id).await?;
+        let b = create("b", Some(a), fx_project_id).await?;
+        let c = create("c", Some(b), fx_project_id).await?;
+        let d = create("d", None, fx_project_id).await?;
+        TaskBmc::set_parent(&ctx, &mm, d, Some(a)).await?;
+        let task_u = TaskForUpdate {
+            done: Some(true),
+            ..Default::default()
+        };
+        TaskBmc::update(&ctx, &mm, c, task_u).await?;
 
-        let tasks: Vec<_> = tasks
-            .into_iter()
-            .filter(|t| t.title.starts_with("test_list_all_ok-task"))
-            .collect();
-        assert!(tasks.len() == 2, "number of seeded tasks.");
+        // -- Check: tree
+        let tree = TaskBmc::get_tree(&ctx, &mm, a).await?;
+        assert_eq!((tree.descendant_count, tree.descendant_done_count), (3, 1));
+        let child_ids: Vec<i64> = tree.children.iter().map(|t| t.task.id).collect();
+        assert_eq!(child_ids, &[b, d]);
+        assert_eq!(tree.children[0].children[0].task.id, c);
 
-        for task in tasks.iter() {
-            TaskBmc::delete(&ctx, &mm, task.id).await?;
+        // -- Check: invalid parents
+        for (id, parent_id) in [(a, c), (a, a)] {
+            let res = TaskBmc::set_parent(&ctx, &mm, id, Some(parent_id)).await;
+            assert!(
+                matches!(res, Err(Error::TaskParentCycle { .. })),
+                "set_parent should fail with TaskParentCycle"
+            );
         }
+        let res = create("e", Some(a), fx_other_project_id).await;
+        assert!(
+            matches!(res, Err(Error::TaskParentOtherProject { parent_id, .. }) if parent_id == a),
+            "create should fail with TaskParentOtherProject"
+        );
+
+        // -- Check: the assignee of the root only sees its own subtasks
+        let ctx_demo1 = Ctx::new(1000)?;
+        TaskBmc::assign(&ctx, &mm, a, Some(1000)).await?;
+        TaskBmc::assign(&ctx, &mm, d, Some(1000)).await?;
+        let tree = TaskBmc::get_tree(&ctx_demo1, &mm, a).await?;
+        let child_ids: Vec<i64> = tree.children.iter().map(|t| t.task.id).collect();
+        assert_eq!(child_ids, &[d]);
+        assert_eq!(tree.descendant_count, 1);
+
+        // -- Check: delete reparents the subtasks
+        TaskBmc::delete(&ctx, &mm, b).await?;
+        let tree = TaskBmc::get_tree(&ctx, &mm, a).await?;
+        let child_ids: Vec<i64> = tree.children.iter().map(|t| t.task.id).collect();
+        assert_eq!(child_ids, &[c, d]);
+        assert_eq!((tree.descendant_count, tree.descendant_done_count), (2, 1));
+        let history = TaskBmc::get_history(&ctx, &mm, c).await?;
+        let reparent = history.last().context("Should have the reparent history")?;
+        assert_eq!(reparent.op, "update");
+        assert_eq!(reparent.before, json!({"parent_id": b}));
+        assert_eq!(reparent.after, json!({"parent_id": a}));
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        ProjectBmc::delete(&ctx, &mm, fx_other_project_id).await?;
+
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_list_by_filter_ok() -> Result<()> {
+    async fn test_dependency_ok() -> Result<()> {
         let mm = _dev_utils::init_test().await;
         let ctx = Ctx::root_ctx();
-        let fx_titles = &[
-            "test_list_by_filter_ok-task 01.a",
-            "test_list_by_filter_ok-task 01.b",
-            "test_list_by_filter_ok-task 02.a",
-            "test_list_by_filter_ok-task 02.b",
-            "test_list_by_filter_ok-task 03",
-        ];
-        seed_tasks(&ctx, &mm, fx_titles).await?;
+        let fx_project_id = seed_project(&ctx, &mm, "test_dependency_ok project").await?;
+        let fx_tasks = seed_tasks(&ctx, &mm, fx_project_id, &["a", "b", "c"]).await?;
+        let (a, b, c) = (fx_tasks[0].id, fx_tasks[1].id, fx_tasks[2].id);
+        let set_done = |id: i64, done: bool| {
+            let task_u = TaskForUpdate {
+                done: Some(done),
+                ..Default::default()
+            };
+            TaskBmc::update(&ctx, &mm, id, task_u)
+        };
+        let (ctx_ref, mm_ref) = (&ctx, &mm);
+        let is_blocked = move |id: i64| async move {
+            let task = TaskBmc::get(ctx_ref, mm_ref, id).await?;
+            Ok::<_, Error>(task.blocked)
+        };
 
-        let filters: Vec<TaskFilter> = serde_json::from_value(json!([
-        {
-            "title": {
-                "$endsWithAny": [".a", ".b"],
-            }
-        },
-        {
-            "title": {
-                "$contains": "03"
-            }
+        // -- Exec: a blocked by b, b blocked by c
+        TaskBmc::add_dependency(&ctx, &mm, a, b).await?;
+        TaskBmc::add_dependency(&ctx, &mm, b, c).await?;
+
+        // -- Check: cycles
+        for (task_id, blocker_id) in [(c, a), (a, a)] {
+            let res = TaskBmc::add_dependency(&ctx, &mm, task_id, blocker_id).await;
+            assert!(
+                matches!(res, Err(Error::TaskDependencyCycle { .. })),
+                "add_dependency should fail with TaskDependencyCycle"
+            );
         }
-        ]))?;
-        let len = 3;
-        let list_options: ListOptions = serde_json::from_value(json!({
-            "limit": len,
-            "order_bys": "!id",
-        }))?;
-        let tasks = TaskBmc::list(&ctx, &mm, Some(filters), Some(list_options)).await?;
-        assert!(tasks.len() == len, "number of seeded tasks.");
 
-        // Cleanup
-        let tasks = TaskBmc::list(
+        // -- Check: blocked
+        assert!(is_blocked(a).await? && is_blocked(b).await? && !is_blocked(c).await?);
+        let res = set_done(a, true).await;
+        assert!(
+            matches!(res, Err(Error::TaskBlocked { id }) if id == a),
+            "done should fail with TaskBlocked"
+        );
+
+        // -- Check: the blockers done unblock the tasks
+        set_done(c, true).await?;
+        assert!(is_blocked(a).await? && !is_blocked(b).await?);
+        set_done(b, true).await?;
+        assert!(!is_blocked(a).await?);
+        set_done(a, true).await?;
+
+        // -- Check: only the owner of the task
+        let ctx_demo1 = Ctx::new(1000)?;
+        TaskBmc::assign(&ctx, &mm, a, Some(1000)).await?;
+        let res = TaskBmc::add_dependency(&ctx_demo1, &mm, a, c).await;
+        assert!(
+            matches!(res, Err(Error::AccessDenied { id, .. }) if id == a),
+            "add_dependency should fail with AccessDenied"
+        );
+        let res = TaskBmc::remove_dependency(&ctx_demo1, &mm, a, b).await;
+        assert!(
+            matches!(res, Err(Error::AccessDenied { id, .. }) if id == a),
+            "remove_dependency should fail with AccessDenied"
+        );
+
+        // -- Check: remove
+        set_done(b, false).await?;
+        assert!(is_blocked(a).await?);
+        TaskBmc::remove_dependency(&ctx, &mm, a, b).await?;
+        assert!(!is_blocked(a).await?);
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_recurrence_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_recurrence_ok project").await?;
+        let fx_label_id = LabelBmc::create(
             &ctx,
             &mm,
-            Some(serde_json::from_value(
-                json!([{"title": {"$startsWith": "test_list_by_filter_ok"}}]),
-            )?),
-            None,
+            LabelForCreate {
+                name: "test_recurrence_ok label".to_string(),
+            },
         )
         .await?;
-        assert_eq!(tasks.len(), 5);
-        for task in tasks {
-            TaskBmc::delete(&ctx, &mm, task.id).await?;
-        }
+        let task_c = TaskForCreate {
+            title: "test_recurrence_ok chore".to_string(),
+            project_id: fx_project_id,
+            due_date: Some(time::macros::date!(2024 - 01 - 31)),
+            recurrence: Some("freq=monthly".to_string()),
+            ..Default::default()
+        };
+        let fx_id = TaskBmc::create(&ctx, &mm, task_c).await?;
+        LabelBmc::attach(&ctx, &mm, fx_id, fx_label_id).await?;
+        let set_done = |done: bool| {
+            let task_u = TaskForUpdate {
+                done: Some(done),
+                ..Default::default()
+            };
+            TaskBmc::update(&ctx, &mm, fx_id, task_u)
+        };
+
+        // -- Exec: done, undone, and done again
+        set_done(true).await?;
+        set_done(false).await?;
+        set_done(true).await?;
+
+        // -- Check: a single next occurrence
+        let filter: TaskFilter = serde_json::from_value(json!({ "project_id": fx_project_id }))?;
+        let tasks = TaskBmc::list(&ctx, &mm, Some(vec![filter]), None).await?;
+        assert_eq!(tasks.len(), 2);
+        let (task, next) = (&tasks[0], &tasks[1]);
+        assert_eq!(task.recurrence.as_deref(), Some("FREQ=MONTHLY"));
+        assert_eq!(task.next_occurrence_id, Some(next.id));
+        assert_eq!(next.title, task.title);
+        assert_eq!(next.due_date, Some(time::macros::date!(2024 - 02 - 29)));
+        assert_eq!(next.recurrence, task.recurrence);
+        assert!(!next.done);
+        let labels = LabelBmc::list_for_task(&ctx, &mm, next.id).await?;
+        assert_eq!(labels.len(), 1);
+        // A single update per done change, and none of the occurrence once created.
+        assert_eq!((task.version, next.version), (3, 0));
+
+        // -- Check: invalid rule
+        let res = TaskBmc::set_recurrence(&ctx, &mm, fx_id, Some("FREQ=HOURLY".to_string())).await;
+        assert!(
+            matches!(res, Err(Error::RecurrenceInvalid { .. })),
+            "RecurrenceInvalid not matching"
+        );
+
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+        LabelBmc::delete(&ctx, &mm, fx_label_id).await?;
 
         Ok(())
     }
 
     #[serial]
     #[tokio::test]
-    async fn test_delete_error_not_found() -> Result<()> {
+    async fn test_history_ok() -> Result<()> {
         let mm = _dev_utils::init_test().await;
-        let ctx = Ctx::root_ctx();
-        let fx_id = 100;
+        let ctx = Ctx::new(1000)?;
+        let fx_project_id = seed_project(&ctx, &mm, "test_history_ok project").await?;
+        let fx_task = seed_tasks(&ctx, &mm, fx_project_id, &["test_history_ok task 01"])
+            .await?
+            .remove(0);
+        let set_title = |title: &str| {
+            let task_u = TaskForUpdate {
+                title: Some(title.to_string()),
+                ..Default::default()
+            };
+            TaskBmc::update(&ctx, &mm, fx_task.id, task_u)
+        };
 
-        let res = TaskBmc::delete(&ctx, &mm, fx_id).await;
+        // -- Exec: rename, rename to the same title (not recorded), delete and restore
+        set_title("test_history_ok task 02").await?;
+        set_title("test_history_ok task 02").await?;
+        TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+        TaskBmc::restore(&ctx, &mm, fx_task.id).await?;
+
+        // -- Check
+        let histories = TaskBmc::get_history(&ctx, &mm, fx_task.id).await?;
+        let ops = histories.iter().map(|h| h.op.as_str()).collect::<Vec<_>>();
+        assert_eq!(ops, ["update", "delete", "restore"]);
+        assert_eq!(
+            histories[0].before,
+            json!({"title": "test_history_ok task 01"})
+        );
+        assert_eq!(
+            histories[0].after,
+            json!({"title": "test_history_ok task 02"})
+        );
+        assert_eq!(histories[0].cid, ctx.user_id());
+        assert_eq!(histories[1].before, json!({"deleted_at": null}));
+        assert!(histories[1].after["deleted_at"].is_string());
+
+        // -- Check: not visible to another user
+        let res = TaskBmc::get_history(&Ctx::new(1001)?, &mm, fx_task.id).await;
         assert!(
-            matches!(
-                res,
-                Err(Error::EntityNotFound {
-                    entity: "task",
-                    id: 100
-                })
-            ),
+            matches!(res, Err(Error::EntityNotFound { .. })),
             "EntityNotFound not matching"
         );
 
+        // -- Clean
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_by_timestamps_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_list_by_timestamps_ok project").await?;
+        let fx_tasks = seed_tasks(
+            &ctx,
+            &mm,
+            fx_project_id,
+            &[
+                "test_list_by_timestamps_ok task 01",
+                "test_list_by_timestamps_ok task 02",
+                "test_list_by_timestamps_ok task 03",
+            ],
+        )
+        .await?;
+        let fx_ctime = lib_utils::time::format_time(fx_tasks[1].ctime);
+
+        let filters: Vec<TaskFilter> = serde_json::from_value(json!([{
+            "project_id": fx_project_id,
+            "ctime": {"$gte": fx_ctime},
+        }]))?;
+        let list_options: ListOptions = serde_json::from_value(json!({
+            "order_bys": "!ctime",
+        }))?;
+        let tasks = TaskBmc::list(&ctx, &mm, Some(filters), Some(list_options)).await?;
+
+        let ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![fx_tasks[2].id, fx_tasks[1].id]);
+
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_soft_delete_restore_purge_ok() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_project_id = seed_project(&ctx, &mm, "test_soft_delete project").await?;
+        let fx_task = seed_tasks(&ctx, &mm, fx_project_id, &["test_soft_delete task 01"])
+            .await?
+            .remove(0);
+
+        // Delete only moves the task to the trash.
+        TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+        let res = TaskBmc::get(&ctx, &mm, fx_task.id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "deleted task should be hidden"
+        );
+        let task = TaskBmc::get_deleted(&ctx, &mm, fx_task.id).await?;
+        assert!(task.deleted_at.is_some());
+        let filters: Vec<TaskFilter> =
+            serde_json::from_value(json!([{"project_id": fx_project_id}]))?;
+        let tasks = TaskBmc::list_deleted(&ctx, &mm, Some(filters), None).await?;
+        assert_eq!(tasks.len(), 1);
+
+        // Restore
+        TaskBmc::restore(&ctx, &mm, fx_task.id).await?;
+        let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+        assert!(task.deleted_at.is_none());
+
+        // Purge only works on the trash.
+        let res = TaskBmc::purge(&ctx, &mm, fx_task.id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "live task should not be purged"
+        );
+        TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+        TaskBmc::purge(&ctx, &mm, fx_task.id).await?;
+        let res = TaskBmc::get_deleted(&ctx, &mm, fx_task.id).await;
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+            "purged task should be gone"
+        );
+
+        ProjectBmc::delete(&ctx, &mm, fx_project_id).await?;
+
         Ok(())
     }
 }
@@ -1,5 +1,5 @@
-use modql::field::{Fields, HasFields};
-use sea_query::{Expr, Iden, PostgresQueryBuilder, Query, SimpleExpr};
+use modql::field::{Field, Fields, HasFields};
+use sea_query::{Expr, Iden, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder;
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgRow, prelude::FromRow};
@@ -9,7 +9,7 @@ use crate::ctx::Ctx;
 use lib_auth::pwd::{self, ContentToHash};
 
 use super::{
-    base::{self, DbBmc},
+    base::{self, add_timestamps_for_update, DbBmc},
     ModelManager, Result,
 };
 
@@ -25,11 +25,6 @@ pub struct UserForCreate {
     pub pwd_clear: String,
 }
 
-#[derive(Fields)]
-struct UserForInsert {
-    username: String,
-}
-
 #[derive(Clone, FromRow, Fields, Debug)]
 pub struct UserForLogin {
     pub id: i64,
@@ -69,6 +64,10 @@ pub struct UserBmc;
 
 impl DbBmc for UserBmc {
     const TABLE: &'static str = "user";
+
+    fn has_timestamps() -> bool {
+        true
+    }
 }
 
 impl UserBmc {
@@ -87,8 +86,6 @@ impl UserBmc {
     where
         E: UserBy,
     {
-        let db = mm.db();
-
         let mut query = Query::select();
         query
             .from(Self::table_ref())
@@ -96,16 +93,13 @@ impl UserBmc {
             .and_where(Expr::col(UserIden::Username).eq(username));
 
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let user = sqlx::query_as_with::<_, E, _>(&sql, values)
-            .fetch_optional(db)
-            .await?;
+        let sqlx_query = sqlx::query_as_with::<_, E, _>(&sql, values);
+        let user = mm.dbx().fetch_optional(sqlx_query).await?;
 
         Ok(user)
     }
 
     pub async fn update_pwd(ctx: &Ctx, mm: &ModelManager, id: i64, pwd_clear: &str) -> Result<()> {
-        let db = mm.db();
-
         let user: UserForLogin = Self::get(ctx, mm, id).await?;
         let pwd = pwd::hash_pwd(ContentToHash {
             content: pwd_clear.into(),
@@ -113,14 +107,18 @@ impl UserBmc {
         })
         .await?;
 
+        let mut fields = Fields::new(vec![Field::new(UserIden::Pwd, pwd.into())]);
+        add_timestamps_for_update(&mut fields, ctx.user_id());
+
         let mut query = Query::update();
         query
             .table(Self::table_ref())
-            .value(UserIden::Pwd, SimpleExpr::from(pwd))
+            .values(fields.for_sea_update())
             .and_where(Expr::col(UserIden::Id).eq(id));
 
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        sqlx::query_with(&sql, values).execute(db).await?;
+        let sqlx_query = sqlx::query_with(&sql, values);
+        mm.dbx().execute(sqlx_query).await?;
 
         Ok(())
     }
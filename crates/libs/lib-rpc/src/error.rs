@@ -20,6 +20,7 @@ pub enum Error {
     FailJsonParams {
         rpc_method: String,
     },
+    ParamsIdsOrFiltersRequired,
 
     // -- Modules
     #[from]
@@ -9,6 +9,6 @@ pub mod router;
 
 pub use self::error::{Error, Result};
 pub use params::*;
-pub use resources::RpcResources;
+pub use resources::{ModelManagerTxn, RpcResources};
 pub use router::RpcRequest;
 pub use rpcs::*;
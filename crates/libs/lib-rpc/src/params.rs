@@ -7,6 +7,7 @@
 //!`IntoParams` or `IntoDefaultParams` are implemented to enxure these `Params` conform to the
 //!`RpcRouter` model.
 
+use lib_core::model::PageOptions;
 use modql::filter::ListOptions;
 use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
@@ -21,14 +22,68 @@ pub struct ParamsForCreate<D> {
 
 impl<D> IntoParams for ParamsForCreate<D> where D: DeserializeOwned + Send {}
 
+#[derive(Deserialize)]
+pub struct ParamsForCreateMany<D> {
+    pub data: Vec<D>,
+}
+
+impl<D> IntoParams for ParamsForCreateMany<D> where D: DeserializeOwned + Send {}
+
 #[derive(Deserialize)]
 pub struct ParamsForUpdate<D> {
     pub id: i64,
+    /// The `version` of the entity the client last read.
+    /// When provided, the update fails if the entity has been modified since.
+    pub version: Option<i64>,
     pub data: D,
 }
 
 impl<D> IntoParams for ParamsForUpdate<D> where D: DeserializeOwned + Send {}
 
+/// Same as `ParamsForUpdate`, for the entities without a `version` (a `version` param is
+/// rejected rather than ignored).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamsForUpdateUnversioned<D> {
+    pub id: i64,
+    pub data: D,
+}
+
+impl<D> IntoParams for ParamsForUpdateUnversioned<D> where D: DeserializeOwned + Send {}
+
+/// Updates either the entities of the `ids` or the ones matching the `filters`
+/// (exactly one of the two).
+#[serde_as]
+#[derive(Deserialize)]
+pub struct ParamsForUpdateMany<D, F>
+where
+    F: DeserializeOwned,
+{
+    pub ids: Option<Vec<i64>>,
+    #[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
+    pub filters: Option<Vec<F>>,
+    pub data: D,
+}
+
+impl<D, F> IntoParams for ParamsForUpdateMany<D, F>
+where
+    D: DeserializeOwned + Send,
+    F: DeserializeOwned + Send + Default,
+{
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+pub struct ParamsFiltered<F>
+where
+    F: DeserializeOwned,
+{
+    #[serde_as(deserialize_as = "OneOrMany<_>")]
+    pub filters: Vec<F>,
+}
+
+impl<F> IntoParams for ParamsFiltered<F> where F: DeserializeOwned + Send {}
+
 #[derive(Deserialize)]
 pub struct ParamsIded {
     pub id: i64,
@@ -46,10 +101,28 @@ where
     #[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
     pub filters: Option<Vec<F>>,
     pub list_options: Option<ListOptions>,
+    /// The page options (`with_total`, `cursor`), at the same level as the `list_options`.
+    #[serde(flatten)]
+    pub page_options: PageOptions,
 }
 
 impl<D> IntoDefaultParams for ParamsList<D> where D: DeserializeOwned + Send + Default {}
 
+#[serde_as]
+#[derive(Deserialize)]
+pub struct ParamsSearch<F>
+where
+    F: DeserializeOwned,
+{
+    /// The full-text search query (web search syntax, e.g., `"fix login" -mobile`).
+    pub query: String,
+    #[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
+    pub filters: Option<Vec<F>>,
+    pub list_options: Option<ListOptions>,
+}
+
+impl<F> IntoParams for ParamsSearch<F> where F: DeserializeOwned + Send + Default {}
+
 // Generic Implementation
 /// Implements `IntoParams` for any type that also implements ` IntoParams`.
 ///
@@ -1,3 +1,6 @@
+use std::ops::Deref;
+use std::sync::OnceLock;
+
 use lib_core::{ctx::Ctx, model::ModelManager};
 
 use crate::{router::FromResources, Result};
@@ -5,6 +8,39 @@ use crate::{router::FromResources, Result};
 pub struct RpcResources {
     pub mm: ModelManager,
     pub ctx: Option<Ctx>,
+    /// The transactional `ModelManager`, created on the first `ModelManagerTxn` request.
+    mm_txn: OnceLock<ModelManager>,
+}
+
+impl RpcResources {
+    pub fn new(mm: ModelManager, ctx: Option<Ctx>) -> Self {
+        RpcResources {
+            mm,
+            ctx,
+            mm_txn: OnceLock::new(),
+        }
+    }
+
+    /// Ends the transaction of the `ModelManagerTxn` (if any) given the handler `result`:
+    /// commits on `Ok` and rolls back on `Err`.
+    pub(crate) async fn finalize_txn<R>(&self, result: Result<R>) -> Result<R> {
+        let Some(mm_txn) = self.mm_txn.get() else {
+            return result;
+        };
+
+        match result {
+            Ok(value) => {
+                mm_txn.commit_txn().await?;
+                Ok(value)
+            }
+            Err(err) => {
+                // NOTE: The handler error is the one to report; if the rollback fails,
+                //       the transaction is rolled back when its connection is dropped anyway.
+                let _ = mm_txn.rollback_txn().await;
+                Err(err)
+            }
+        }
+    }
 }
 
 impl FromResources for Ctx {
@@ -37,3 +73,29 @@ impl FromResources for ModelManager {
         Ok(rpc_resources.mm.clone())
     }
 }
+
+/// A `ModelManager` running all the calls of an RPC handler in one transaction,
+/// committed if the handler returns `Ok` and rolled back if it returns `Err`.
+///
+/// e.g., `pub async fn create_task(ctx: Ctx, mm: ModelManagerTxn, params: ...)`
+pub struct ModelManagerTxn(ModelManager);
+
+impl Deref for ModelManagerTxn {
+    type Target = ModelManager;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromResources for ModelManagerTxn {
+    fn from_resources(rpc_resources: &RpcResources) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mm = rpc_resources
+            .mm_txn
+            .get_or_init(|| rpc_resources.mm.new_with_txn());
+        Ok(ModelManagerTxn(mm.clone()))
+    }
+}
@@ -4,11 +4,11 @@
 //!
 //! - `RpcRouter` holds the Hashmap of `method_name` and `Box<dyn RpcHandlerWrapperTrait>`.
 //! - `RpcHandler` trait is implemented for any async function that, with `(S1, S2, ... [impl IntoParams])`
-//! (see router/from_resources.rs and src/resources.rs).
+//!   (see router/from_resources.rs and src/resources.rs).
 //! - `IntoParams` is the trait to implement to get the conversion from `Option<Value>` json-rpc
-//! params to the handler's Param types.
+//!   params to the handler's Param types.
 //! - `IntoParams` has a default `into_params` implementation taht wil return an error if the
-//! params are missing.
+//!   params are missing.
 //! ```
 //! #[derive(Deserialize)]
 //! pub struct ParamsIded {
@@ -19,7 +19,7 @@
 //! ```
 //! - Implement `IntoParams::into_params` function for custom behavior.
 //! - Implementing `IntoDefaultParams` on a type that implements `Default` will auto implement
-//! `IntoParams` and call `T::default()` when the params `Option<Value>` is None.
+//!   `IntoParams` and call `T::default()` when the params `Option<Value>` is None.
 
 use std::{collections::HashMap, pin::Pin};
 
@@ -22,6 +22,8 @@ use super::RpcHandlerWrapperTrait;
 ///   with none or negligible performance impact, thus facilitating the use of RpcRoute dynamic dispatch.
 /// - `T` is the tuple of `impl FromResources` arguments.
 /// - `P` is the `impl IntoParams` argument.
+/// - When one of the `FromResources` arguments is a `ModelManagerTxn`, its transaction is committed
+///   if the handler returns `Ok` and rolled back otherwise.
 pub trait RpcHandler<T, P, R>: Clone
 where
     T: Send + Sync + 'static,
@@ -72,7 +74,8 @@ macro_rules! impl_rpc_handler_pair {
                         $( $T::from_resources(&rpc_resources)?, )*
                         param,
                     )
-                    .await?;
+                    .await;
+                    let result = rpc_resources.finalize_txn(result).await?;
                     Ok(serde_json::to_value(result)?)
                 })
             }
@@ -93,7 +96,8 @@ macro_rules! impl_rpc_handler_pair {
                     let result = self(
                         $( $T::from_resources(&rpc_resources)?, )*
                     )
-                    .await?;
+                    .await;
+                    let result = rpc_resources.finalize_txn(result).await?;
                     Ok(serde_json::to_value(result)?)
                 })
             }
@@ -0,0 +1,84 @@
+use lib_core::{
+    ctx::Ctx,
+    model::{
+        comment::{Comment, CommentBmc, CommentFilter, CommentForCreate, CommentForUpdate},
+        ModelManager,
+    },
+};
+use modql::filter::ListOptions;
+use serde::Deserialize;
+use serde_with::{serde_as, OneOrMany};
+
+use crate::Result;
+use crate::{
+    params::{ParamsForCreate, ParamsForUpdateUnversioned, ParamsIded},
+    resources::ModelManagerTxn,
+    router::{IntoParams, RpcRouter},
+    rpc_router,
+};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(add_comment, list_comments, update_comment, delete_comment)
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+pub struct ParamsListComments {
+    pub task_id: i64,
+    #[serde_as(deserialize_as = "Option<OneOrMany<_>>")]
+    pub filters: Option<Vec<CommentFilter>>,
+    pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsListComments {}
+
+/// Adds a comment to the task, with the `Ctx` user as author.
+pub async fn add_comment(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForCreate<CommentForCreate>,
+) -> Result<Comment> {
+    let ParamsForCreate { data } = params;
+
+    let id = CommentBmc::create(&ctx, &mm, data).await?;
+    let comment = CommentBmc::get(&ctx, &mm, id).await?;
+
+    Ok(comment)
+}
+
+pub async fn list_comments(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsListComments,
+) -> Result<Vec<Comment>> {
+    let ParamsListComments {
+        task_id,
+        filters,
+        list_options,
+    } = params;
+
+    let comments = CommentBmc::list_for_task(&ctx, &mm, task_id, filters, list_options).await?;
+    Ok(comments)
+}
+
+pub async fn update_comment(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForUpdateUnversioned<CommentForUpdate>,
+) -> Result<Comment> {
+    let ParamsForUpdateUnversioned { id, data } = params;
+
+    CommentBmc::update(&ctx, &mm, id, data).await?;
+    let comment = CommentBmc::get(&ctx, &mm, id).await?;
+
+    Ok(comment)
+}
+
+pub async fn delete_comment(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Comment> {
+    let ParamsIded { id } = params;
+
+    let comment = CommentBmc::get(&ctx, &mm, id).await?;
+    CommentBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(comment)
+}
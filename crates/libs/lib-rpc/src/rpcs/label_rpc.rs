@@ -0,0 +1,132 @@
+use lib_core::{
+    ctx::Ctx,
+    model::{
+        label::{Label, LabelBmc, LabelFilter, LabelForCreate, LabelForUpdate},
+        ListPage, ModelManager,
+    },
+};
+use serde::Deserialize;
+
+use crate::Result;
+use crate::{
+    params::{ParamsForCreate, ParamsForUpdateUnversioned, ParamsIded, ParamsList},
+    resources::ModelManagerTxn,
+    router::{IntoParams, RpcRouter},
+    rpc_router,
+};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        create_label,
+        get_label,
+        list_labels,
+        update_label,
+        delete_label,
+        attach_label,
+        detach_label,
+        list_task_labels
+    )
+}
+
+#[derive(Deserialize)]
+pub struct ParamsTaskLabel {
+    pub task_id: i64,
+    pub label_id: i64,
+}
+
+impl IntoParams for ParamsTaskLabel {}
+
+pub async fn create_label(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForCreate<LabelForCreate>,
+) -> Result<Label> {
+    let ParamsForCreate { data } = params;
+
+    let id = LabelBmc::create(&ctx, &mm, data).await?;
+    let label = LabelBmc::get(&ctx, &mm, id).await?;
+
+    Ok(label)
+}
+
+pub async fn get_label(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Label> {
+    let ParamsIded { id } = params;
+    let label = LabelBmc::get(&ctx, &mm, id).await?;
+    Ok(label)
+}
+
+pub async fn list_labels(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<LabelFilter>,
+) -> Result<ListPage<Label>> {
+    let page = LabelBmc::list_page(
+        &ctx,
+        &mm,
+        params.filters,
+        params.list_options,
+        params.page_options,
+    )
+    .await?;
+    Ok(page)
+}
+
+pub async fn update_label(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForUpdateUnversioned<LabelForUpdate>,
+) -> Result<Label> {
+    let ParamsForUpdateUnversioned { id, data } = params;
+
+    LabelBmc::update(&ctx, &mm, id, data).await?;
+    let label = LabelBmc::get(&ctx, &mm, id).await?;
+
+    Ok(label)
+}
+
+pub async fn delete_label(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Label> {
+    let ParamsIded { id } = params;
+
+    let label = LabelBmc::get(&ctx, &mm, id).await?;
+    LabelBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(label)
+}
+
+/// Attaches the label to the task, and returns the labels of the task.
+pub async fn attach_label(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsTaskLabel,
+) -> Result<Vec<Label>> {
+    let ParamsTaskLabel { task_id, label_id } = params;
+
+    LabelBmc::attach(&ctx, &mm, task_id, label_id).await?;
+    let labels = LabelBmc::list_for_task(&ctx, &mm, task_id).await?;
+
+    Ok(labels)
+}
+
+/// Detaches the label from the task, and returns the labels of the task.
+pub async fn detach_label(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsTaskLabel,
+) -> Result<Vec<Label>> {
+    let ParamsTaskLabel { task_id, label_id } = params;
+
+    LabelBmc::detach(&ctx, &mm, task_id, label_id).await?;
+    let labels = LabelBmc::list_for_task(&ctx, &mm, task_id).await?;
+
+    Ok(labels)
+}
+
+pub async fn list_task_labels(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Vec<Label>> {
+    let ParamsIded { id } = params;
+    let labels = LabelBmc::list_for_task(&ctx, &mm, id).await?;
+    Ok(labels)
+}
@@ -1,2 +1,4 @@
+pub mod comment_rpc;
+pub mod label_rpc;
 pub mod project_rpc;
 pub mod task_rpc;
@@ -1,67 +1,82 @@
-// use lib_core::{ctx::Ctx, model::ModelManager};
-//
-// use crate::Result;
-// use crate::{
-//     params::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList},
-//     router::RpcRouter,
-//     rpc_router,
-// };
-//
-// pub fn rpc_router() -> RpcRouter {
-//     rpc_router!(
-//         create_project,
-//         list_projects,
-//         update_project,
-//         delete_project
-//     )
-// }
-//
-// pub async fn create_project(
-//     ctx: Ctx,
-//     mm: ModelManager,
-//     params: ParamsForCreate<ProjectForCreate>,
-// ) -> Result<Project> {
-//     let ParamsForCreate { data } = params;
-//
-//     let id = ProjectBmc::create(&ctx, &mm, data).await?;
-//     let task: Project = ProjectBmc::get(&ctx, &mm, id).await?;
-//
-//     Ok(task)
-// }
-//
-// pub async fn get_project(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Project> {
-//     let ParamsIded { id } = params;
-//     let task = ProjectBmc::get(&ctx, &mm, id).await?;
-//     Ok(task)
-// }
-//
-// pub async fn list_projects(
-//     ctx: Ctx,
-//     mm: ModelManager,
-//     params: ParamsList<ProjectFilter>,
-// ) -> Result<Vec<Project>> {
-//     let tasks = ProjectBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
-//     Ok(tasks)
-// }
-//
-// pub async fn update_project(
-//     ctx: Ctx,
-//     mm: ModelManager,
-//     params: ParamsForUpdate<ProjectForUpdate>,
-// ) -> Result<Project> {
-//     let ParamsForUpdate { id, data } = params;
-//
-//     ProjectBmc::update(&ctx, &mm, id, data).await?;
-//     let task = ProjectBmc::get(&ctx, &mm, id).await?;
-//
-//     Ok(task)
-// }
-//
-// pub async fn delete_project(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Project> {
-//     let ParamsIded { id } = params;
-//
-//     let task = ProjectBmc::get(&ctx, &mm, id).await?;
-//     ProjectBmc::delete(&ctx, &mm, id).await?;
-//
-//     Ok(task)
-// }
+use lib_core::{
+    ctx::Ctx,
+    model::{
+        project::{Project, ProjectBmc, ProjectFilter, ProjectForCreate, ProjectForUpdate},
+        ListPage, ModelManager,
+    },
+};
+
+use crate::Result;
+use crate::{
+    params::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList},
+    resources::ModelManagerTxn,
+    router::RpcRouter,
+    rpc_router,
+};
+
+pub fn rpc_router() -> RpcRouter {
+    rpc_router!(
+        create_project,
+        get_project,
+        list_projects,
+        update_project,
+        delete_project
+    )
+}
+
+pub async fn create_project(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForCreate<ProjectForCreate>,
+) -> Result<Project> {
+    let ParamsForCreate { data } = params;
+
+    let id = ProjectBmc::create(&ctx, &mm, data).await?;
+    let project = ProjectBmc::get(&ctx, &mm, id).await?;
+
+    Ok(project)
+}
+
+pub async fn get_project(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Project> {
+    let ParamsIded { id } = params;
+    let project = ProjectBmc::get(&ctx, &mm, id).await?;
+    Ok(project)
+}
+
+pub async fn list_projects(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<ProjectFilter>,
+) -> Result<ListPage<Project>> {
+    let page = ProjectBmc::list_page(
+        &ctx,
+        &mm,
+        params.filters,
+        params.list_options,
+        params.page_options,
+    )
+    .await?;
+    Ok(page)
+}
+
+pub async fn update_project(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForUpdate<ProjectForUpdate>,
+) -> Result<Project> {
+    let ParamsForUpdate { id, version, data } = params;
+
+    ProjectBmc::update_with_version(&ctx, &mm, id, version, data).await?;
+    let project = ProjectBmc::get(&ctx, &mm, id).await?;
+
+    Ok(project)
+}
+
+pub async fn delete_project(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Project> {
+    let ParamsIded { id } = params;
+
+    let project = ProjectBmc::get(&ctx, &mm, id).await?;
+    ProjectBmc::delete(&ctx, &mm, id).await?;
+
+    Ok(project)
+}
@@ -1,25 +1,52 @@
 use lib_core::{
     ctx::Ctx,
     model::{
-        task::{Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate},
-        ModelManager,
+        history::History,
+        task::{Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate, TaskForUpsert, TaskTree},
+        ListPage, ModelManager, SearchHit,
     },
 };
+use serde::{Deserialize, Serialize};
 
-use crate::Result;
 use crate::{
-    params::{ParamsForCreate, ParamsForUpdate, ParamsIded, ParamsList},
-    router::RpcRouter,
+    params::{
+        ParamsFiltered, ParamsForCreate, ParamsForCreateMany, ParamsForUpdate, ParamsForUpdateMany,
+        ParamsIded, ParamsList, ParamsSearch,
+    },
+    resources::ModelManagerTxn,
+    router::{IntoParams, RpcRouter},
     rpc_router,
 };
+use crate::{Error, Result};
 
 pub fn rpc_router() -> RpcRouter {
-    rpc_router!(create_task, list_tasks, update_task, delete_task)
+    rpc_router!(
+        create_task,
+        create_tasks,
+        upsert_task,
+        list_tasks,
+        list_my_tasks,
+        search_tasks,
+        update_task,
+        assign_task,
+        set_task_parent,
+        get_task_tree,
+        get_task_history,
+        set_task_recurrence,
+        add_dependency,
+        remove_dependency,
+        update_tasks,
+        delete_task,
+        delete_tasks,
+        list_deleted_tasks,
+        restore_task,
+        purge_task
+    )
 }
 
 pub async fn create_task(
     ctx: Ctx,
-    mm: ModelManager,
+    mm: ModelManagerTxn,
     params: ParamsForCreate<TaskForCreate>,
 ) -> Result<Task> {
     let ParamsForCreate { data } = params;
@@ -30,6 +57,44 @@ pub async fn create_task(
     Ok(task)
 }
 
+/// Creates all the tasks or none.
+pub async fn create_tasks(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForCreateMany<TaskForCreate>,
+) -> Result<Vec<Task>> {
+    let ParamsForCreateMany { data } = params;
+
+    let ids = TaskBmc::create_many(&ctx, &mm, data).await?;
+    let tasks = TaskBmc::list_by_ids(&ctx, &mm, &ids).await?;
+
+    Ok(tasks)
+}
+
+#[derive(Serialize)]
+pub struct TaskUpserted {
+    pub task: Task,
+    /// `true` if the task was created, `false` if it was updated.
+    pub inserted: bool,
+}
+
+/// Creates or updates the task by its `external_ref`.
+pub async fn upsert_task(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForCreate<TaskForUpsert>,
+) -> Result<TaskUpserted> {
+    let ParamsForCreate { data } = params;
+
+    let upserted = TaskBmc::upsert(&ctx, &mm, data).await?;
+    let task = TaskBmc::get(&ctx, &mm, upserted.id).await?;
+
+    Ok(TaskUpserted {
+        task,
+        inserted: upserted.inserted,
+    })
+}
+
 pub async fn get_task(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Task> {
     let ParamsIded { id } = params;
     let task = TaskBmc::get(&ctx, &mm, id).await?;
@@ -40,25 +105,205 @@ pub async fn list_tasks(
     ctx: Ctx,
     mm: ModelManager,
     params: ParamsList<TaskFilter>,
-) -> Result<Vec<Task>> {
-    let tasks = TaskBmc::list(&ctx, &mm, params.filters, params.list_options).await?;
-    Ok(tasks)
+) -> Result<ListPage<Task>> {
+    let page = TaskBmc::list_page(
+        &ctx,
+        &mm,
+        params.filters,
+        params.list_options,
+        params.page_options,
+    )
+    .await?;
+    Ok(page)
 }
 
-pub async fn update_task(
+/// Lists the tasks assigned to the `Ctx` user (matching the `filters`).
+pub async fn list_my_tasks(
     ctx: Ctx,
     mm: ModelManager,
+    params: ParamsList<TaskFilter>,
+) -> Result<ListPage<Task>> {
+    let page = TaskBmc::list_assigned_page(
+        &ctx,
+        &mm,
+        params.filters,
+        params.list_options,
+        params.page_options,
+    )
+    .await?;
+    Ok(page)
+}
+
+pub async fn search_tasks(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsSearch<TaskFilter>,
+) -> Result<Vec<SearchHit<Task>>> {
+    let ParamsSearch {
+        query,
+        filters,
+        list_options,
+    } = params;
+
+    let hits = TaskBmc::search(&ctx, &mm, &query, filters, list_options).await?;
+
+    Ok(hits)
+}
+
+pub async fn update_task(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
     params: ParamsForUpdate<TaskForUpdate>,
 ) -> Result<Task> {
-    let ParamsForUpdate { id, data } = params;
+    let ParamsForUpdate { id, version, data } = params;
+
+    TaskBmc::update_with_version(&ctx, &mm, id, version, data).await?;
+    let task = TaskBmc::get(&ctx, &mm, id).await?;
+
+    Ok(task)
+}
+
+#[derive(Deserialize)]
+pub struct ParamsAssign {
+    pub id: i64,
+    /// The user to assign the task to, or `null` to unassign it.
+    pub assignee_id: Option<i64>,
+}
+
+impl IntoParams for ParamsAssign {}
+
+pub async fn assign_task(ctx: Ctx, mm: ModelManagerTxn, params: ParamsAssign) -> Result<Task> {
+    let ParamsAssign { id, assignee_id } = params;
+
+    TaskBmc::assign(&ctx, &mm, id, assignee_id).await?;
+    let task = TaskBmc::get(&ctx, &mm, id).await?;
+
+    Ok(task)
+}
+
+#[derive(Deserialize)]
+pub struct ParamsSetParent {
+    pub id: i64,
+    /// The new parent task, or `null` to make the task a top-level task.
+    pub parent_id: Option<i64>,
+}
+
+impl IntoParams for ParamsSetParent {}
+
+pub async fn set_task_parent(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsSetParent,
+) -> Result<Task> {
+    let ParamsSetParent { id, parent_id } = params;
 
-    TaskBmc::update(&ctx, &mm, id, data).await?;
+    TaskBmc::set_parent(&ctx, &mm, id, parent_id).await?;
     let task = TaskBmc::get(&ctx, &mm, id).await?;
 
     Ok(task)
 }
 
-pub async fn delete_task(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<Task> {
+/// Returns the task with all its subtasks, recursively.
+pub async fn get_task_tree(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Result<TaskTree> {
+    let ParamsIded { id } = params;
+    let tree = TaskBmc::get_tree(&ctx, &mm, id).await?;
+    Ok(tree)
+}
+
+/// Returns the changes of the task (oldest first).
+pub async fn get_task_history(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsIded,
+) -> Result<Vec<History>> {
+    let ParamsIded { id } = params;
+    let histories = TaskBmc::get_history(&ctx, &mm, id).await?;
+    Ok(histories)
+}
+
+#[derive(Deserialize)]
+pub struct ParamsSetRecurrence {
+    pub id: i64,
+    /// The recurrence rule (e.g., `FREQ=WEEKLY;INTERVAL=2`), or `null` to stop the recurrence.
+    pub recurrence: Option<String>,
+}
+
+impl IntoParams for ParamsSetRecurrence {}
+
+pub async fn set_task_recurrence(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsSetRecurrence,
+) -> Result<Task> {
+    let ParamsSetRecurrence { id, recurrence } = params;
+
+    TaskBmc::set_recurrence(&ctx, &mm, id, recurrence).await?;
+    let task = TaskBmc::get(&ctx, &mm, id).await?;
+
+    Ok(task)
+}
+
+#[derive(Deserialize)]
+pub struct ParamsDependency {
+    pub task_id: i64,
+    pub blocker_id: i64,
+}
+
+impl IntoParams for ParamsDependency {}
+
+/// Makes the task blocked by the blocker task, and returns the task.
+pub async fn add_dependency(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsDependency,
+) -> Result<Task> {
+    let ParamsDependency {
+        task_id,
+        blocker_id,
+    } = params;
+
+    TaskBmc::add_dependency(&ctx, &mm, task_id, blocker_id).await?;
+    let task = TaskBmc::get(&ctx, &mm, task_id).await?;
+
+    Ok(task)
+}
+
+/// Removes the dependency of the task on the blocker task, and returns the task.
+pub async fn remove_dependency(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsDependency,
+) -> Result<Task> {
+    let ParamsDependency {
+        task_id,
+        blocker_id,
+    } = params;
+
+    TaskBmc::remove_dependency(&ctx, &mm, task_id, blocker_id).await?;
+    let task = TaskBmc::get(&ctx, &mm, task_id).await?;
+
+    Ok(task)
+}
+
+/// Updates all the tasks of the `ids` (or matching the `filters`), or none.
+pub async fn update_tasks(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsForUpdateMany<TaskForUpdate, TaskFilter>,
+) -> Result<Vec<Task>> {
+    let ParamsForUpdateMany { ids, filters, data } = params;
+
+    let ids = match (ids, filters) {
+        (Some(ids), None) => TaskBmc::update_many(&ctx, &mm, &ids, data).await?,
+        (None, Some(filters)) => TaskBmc::update_many_by_filter(&ctx, &mm, filters, data).await?,
+        _ => return Err(Error::ParamsIdsOrFiltersRequired),
+    };
+    let tasks = TaskBmc::list_by_ids(&ctx, &mm, &ids).await?;
+
+    Ok(tasks)
+}
+
+pub async fn delete_task(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Task> {
     let ParamsIded { id } = params;
 
     let task = TaskBmc::get(&ctx, &mm, id).await?;
@@ -66,3 +311,55 @@ pub async fn delete_task(ctx: Ctx, mm: ModelManager, params: ParamsIded) -> Resu
 
     Ok(task)
 }
+
+#[derive(Serialize)]
+pub struct TasksDeleted {
+    pub count: u64,
+}
+
+/// Moves all the tasks matching the `filters` to the trash.
+pub async fn delete_tasks(
+    ctx: Ctx,
+    mm: ModelManagerTxn,
+    params: ParamsFiltered<TaskFilter>,
+) -> Result<TasksDeleted> {
+    let ParamsFiltered { filters } = params;
+
+    let count = TaskBmc::delete_many(&ctx, &mm, filters).await?;
+
+    Ok(TasksDeleted { count })
+}
+
+pub async fn list_deleted_tasks(
+    ctx: Ctx,
+    mm: ModelManager,
+    params: ParamsList<TaskFilter>,
+) -> Result<ListPage<Task>> {
+    let page = TaskBmc::list_deleted_page(
+        &ctx,
+        &mm,
+        params.filters,
+        params.list_options,
+        params.page_options,
+    )
+    .await?;
+    Ok(page)
+}
+
+pub async fn restore_task(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Task> {
+    let ParamsIded { id } = params;
+
+    TaskBmc::restore(&ctx, &mm, id).await?;
+    let task = TaskBmc::get(&ctx, &mm, id).await?;
+
+    Ok(task)
+}
+
+pub async fn purge_task(ctx: Ctx, mm: ModelManagerTxn, params: ParamsIded) -> Result<Task> {
+    let ParamsIded { id } = params;
+
+    let task = TaskBmc::get_deleted(&ctx, &mm, id).await?;
+    TaskBmc::purge(&ctx, &mm, id).await?;
+
+    Ok(task)
+}
@@ -1,6 +1,21 @@
 use std::time::Duration;
 
-use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use time::format_description::well_known::Rfc3339;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime};
+
+/// The format of the calendar dates (e.g., `2024-01-31`).
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+pub use self::serde_formats::date_format;
+
+mod serde_formats {
+    // Serde module of the calendar dates, e.g., `#[serde(with = "lib_utils::time::date_format")]`
+    // (or `date_format::option` for an `Option<Date>`).
+    // NOTE: In its own module, as the macro code does not expect our `Result` alias.
+    time::serde::format_description!(pub date_format, Date, "[year]-[month]-[day]");
+}
 
 pub fn now_utc() -> OffsetDateTime {
     OffsetDateTime::now_utc()
@@ -20,6 +35,10 @@ pub fn parse_utc(moment: &str) -> Result<OffsetDateTime> {
     OffsetDateTime::parse(moment, &Rfc3339).map_err(|_| Error::DateFailParse(moment.into()))
 }
 
+pub fn parse_date(moment: &str) -> Result<Date> {
+    Date::parse(moment, DATE_FORMAT).map_err(|_| Error::DateFailParse(moment.into()))
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
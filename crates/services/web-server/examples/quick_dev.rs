@@ -21,6 +21,22 @@ async fn main() -> Result<()> {
         }),
     );
 
+    let req_create_project = hc.do_post(
+        "/api/rpc",
+        json!({
+            "id": 1,
+            "method": "create_project",
+            "params": {
+                "data": {
+                    "name": "project AAA"
+                }
+            }
+        }),
+    );
+    let result = req_create_project.await?;
+    result.print().await?;
+    let project_id = result.json_value::<i64>("/result/id")?;
+
     let mut task_ids: Vec<i64> = Vec::new();
     for i in 0..5 {
         let req_create_task = hc.do_post(
@@ -30,6 +46,7 @@ async fn main() -> Result<()> {
                 "method": "create_task",
                 "params": {
                     "data": {
+                        "project_id": project_id,
                         "title": format!{"task AAA {i}"}
                     }
                 }
@@ -71,6 +88,7 @@ async fn main() -> Result<()> {
             "method": "list_tasks",
             "params": {
                     "filters": [{
+                    "project_id": project_id,
                     "title": {"$endsWith": "BB"},
                     "done": false,
                     },{
@@ -78,7 +96,8 @@ async fn main() -> Result<()> {
                     }],
                     "list_options": {
                     "order_bys": "!id"
-                    }
+                    },
+                    "with_total": true
             }
         }),
     );
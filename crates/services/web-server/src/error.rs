@@ -1,5 +1,5 @@
 use derive_more::From;
-use lib_core::model;
+use lib_core::model::{self, migrate};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -11,6 +11,8 @@ pub enum Error {
     // -- Modules
     #[from]
     Model(model::Error),
+    #[from]
+    Migrate(migrate::Error),
 }
 
 // Error Boilerplate
@@ -1,10 +1,13 @@
 use crate::web::{
-    mw_auth, mw_resp_map::mw_response_mapper, routes_login, routes_rpc, routes_static,
+    mw_auth, mw_resp_map::mw_response_mapper, routes_login, routes_rpc, routes_static, routes_ws,
 };
 use axum::{middleware, Router};
+use std::convert::Infallible;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_cookies::CookieManagerLayer;
+use tracing::debug;
 use tracing_subscriber::EnvFilter;
 
 mod config;
@@ -15,7 +18,11 @@ mod web;
 pub use self::error::{Error, Result};
 pub use config::web_config;
 
-use lib_core::{_dev_utils, model::ModelManager};
+use lib_core::{
+    _dev_utils,
+    event::Dispatcher,
+    model::{migrate::Migrator, outbox::Event, ModelManager},
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -28,15 +35,36 @@ async fn main() -> Result<()> {
     // FOR DEV ONLY
     _dev_utils::init_dev().await;
 
+    // Apply the pending db migrations
+    Migrator::init().await?.up().await?;
+
     // init ModelManager
     let mm = ModelManager::init().await?;
 
+    // Deliver the domain events of the outbox to the subscribers (see `lib_core::event`).
+    let ws_state = routes_ws::WsState::new();
+    let ws_events = ws_state.clone();
+    Dispatcher::new(mm.clone())
+        .subscribe("log", |event: Arc<Event>| async move {
+            debug!(
+                "{:<12} - {} {} ({})",
+                "EVENT", event.name, event.entity_id, event.id
+            );
+            Ok::<_, Infallible>(())
+        })
+        .subscribe("ws", move |event: Arc<Event>| {
+            ws_events.publish(event);
+            async { Ok::<_, Infallible>(()) }
+        })
+        .spawn();
+
     // route_layer() adds middleware to existing routes. You first have to add your routes!
     // This will only run if the request matches a route, in this case: "/api/tickets".
     // That means that other routers won't be impacted by this middleware.
     let rpc_state = routes_rpc::RpcState { mm: mm.clone() };
-    let routes_rpc =
-        routes_rpc::routes(rpc_state).route_layer(middleware::from_fn(mw_auth::mw_ctx_require));
+    let routes_rpc = routes_rpc::routes(rpc_state)
+        .merge(routes_ws::routes(ws_state))
+        .route_layer(middleware::from_fn(mw_auth::mw_ctx_require));
 
     // .merge() allows to compose many routers together.
     // .fallback_service() falls back to the static render.
@@ -33,6 +33,11 @@ pub enum Error {
     #[from]
     Token(token::Error),
 
+    // -- Rpc
+    RpcRequestInvalid {
+        reason: String,
+    },
+
     // -- Libs
     #[from]
     Rpc(lib_rpc::Error),
@@ -74,12 +79,23 @@ impl Error {
             | LoginFailUserHasNoPwd { .. } => (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL),
             // -- Auth
             CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
-            // -- Model
-            Model(model::Error::EntityNotFound { entity, id }) => (
+            // -- Rpc
+            RpcRequestInvalid { reason } => (
+                StatusCode::BAD_REQUEST,
+                ClientError::RPC_REQUEST_INVALID {
+                    reason: reason.clone(),
+                },
+            ),
+            Rpc(lib_rpc::Error::ParamsIdsOrFiltersRequired) => (
                 StatusCode::BAD_REQUEST,
-                ClientError::ENTITY_NOT_FOUND { entity, id: *id },
+                ClientError::PARAMS_IDS_OR_FILTERS_REQUIRED,
             ),
 
+            // -- Model
+            Model(model_error) | Rpc(lib_rpc::Error::Model(model_error)) => {
+                model_client_status_and_error(model_error)
+            }
+
             // -- Fallback.
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -89,12 +105,166 @@ impl Error {
     }
 }
 
-#[derive(Debug, strum_macros::AsRefStr, Serialize)]
+/// From the model error (direct, or through an RPC) to the http status code and ClientError
+fn model_client_status_and_error(model_error: &model::Error) -> (StatusCode, ClientError) {
+    use model::Error::*;
+
+    match model_error {
+        EntityNotFound { entity, id } => (
+            StatusCode::BAD_REQUEST,
+            ClientError::ENTITY_NOT_FOUND { entity, id: *id },
+        ),
+        VersionConflict { entity, id, .. } => (
+            StatusCode::CONFLICT,
+            ClientError::VERSION_CONFLICT { entity, id: *id },
+        ),
+        TaskParentCycle { parent_id, .. } | TaskParentOtherProject { parent_id, .. } => (
+            StatusCode::BAD_REQUEST,
+            ClientError::TASK_PARENT_INVALID {
+                parent_id: *parent_id,
+            },
+        ),
+        TaskDependencyCycle {
+            task_id,
+            blocker_id,
+        } => (
+            StatusCode::BAD_REQUEST,
+            ClientError::TASK_DEPENDENCY_CYCLE {
+                task_id: *task_id,
+                blocker_id: *blocker_id,
+            },
+        ),
+        TaskBlocked { id } => (StatusCode::CONFLICT, ClientError::TASK_BLOCKED { id: *id }),
+        RecurrenceInvalid { rule, reason } => (
+            StatusCode::BAD_REQUEST,
+            ClientError::RECURRENCE_INVALID {
+                rule: rule.to_string(),
+                reason: reason.to_string(),
+            },
+        ),
+        AccessDenied { entity, id } => (
+            StatusCode::FORBIDDEN,
+            ClientError::ACCESS_DENIED { entity, id: *id },
+        ),
+        UniqueViolation { table, constraint } => (
+            StatusCode::CONFLICT,
+            ClientError::UNIQUE_VIOLATION {
+                table: table.to_string(),
+                constraint: constraint.to_string(),
+            },
+        ),
+        BulkOverMax { max, actual } => (
+            StatusCode::BAD_REQUEST,
+            ClientError::BULK_OVER_MAX {
+                max: *max,
+                actual: *actual,
+            },
+        ),
+        BulkFilterEmpty => (StatusCode::BAD_REQUEST, ClientError::BULK_FILTER_EMPTY),
+        ListCursorInvalid
+        | ListCursorWithOffset
+        | ListOrderByInvalid(_)
+        | ListLimitOverMax { .. }
+        | ListLimitUnderMin { .. } => (StatusCode::BAD_REQUEST, ClientError::LIST_OPTIONS_INVALID),
+
+        // -- Fallback.
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ClientError::SERVICE_ERROR,
+        ),
+    }
+}
+
+#[derive(Debug, Clone, strum_macros::AsRefStr, Serialize)]
 #[serde(tag = "message", content = "detail")]
 #[allow(non_camel_case_types)]
 pub enum ClientError {
     LOGIN_FAIL,
     NO_AUTH,
+    RPC_REQUEST_INVALID { reason: String },
     ENTITY_NOT_FOUND { entity: &'static str, id: i64 },
+    VERSION_CONFLICT { entity: &'static str, id: i64 },
+    TASK_PARENT_INVALID { parent_id: i64 },
+    TASK_DEPENDENCY_CYCLE { task_id: i64, blocker_id: i64 },
+    TASK_BLOCKED { id: i64 },
+    RECURRENCE_INVALID { rule: String, reason: String },
+    ACCESS_DENIED { entity: &'static str, id: i64 },
+    UNIQUE_VIOLATION { table: String, constraint: String },
+    LIST_OPTIONS_INVALID,
+    BULK_OVER_MAX { max: usize, actual: usize },
+    BULK_FILTER_EMPTY,
+    PARAMS_IDS_OR_FILTERS_REQUIRED,
     SERVICE_ERROR,
 }
+
+impl ClientError {
+    /// Returns the JSON-RPC 2.0 `error.code` of the client error (the application errors are in
+    /// the implementation defined server error range).
+    pub fn rpc_code(&self) -> i64 {
+        match self {
+            ClientError::RPC_REQUEST_INVALID { .. } => -32600,
+            ClientError::LIST_OPTIONS_INVALID
+            | ClientError::BULK_OVER_MAX { .. }
+            | ClientError::BULK_FILTER_EMPTY
+            | ClientError::PARAMS_IDS_OR_FILTERS_REQUIRED => -32602,
+            ClientError::SERVICE_ERROR => -32603,
+            _ => -32000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_error_bulk_over_max() {
+        let error = Error::Model(model::Error::BulkOverMax {
+            max: 1000,
+            actual: 1001,
+        });
+
+        let (status, client_error) = error.client_status_and_error();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(matches!(
+            client_error,
+            ClientError::BULK_OVER_MAX {
+                max: 1000,
+                actual: 1001
+            }
+        ));
+    }
+
+    #[test]
+    fn test_client_error_params_ids_or_filters_required() {
+        let error = Error::Rpc(lib_rpc::Error::ParamsIdsOrFiltersRequired);
+
+        let (status, client_error) = error.client_status_and_error();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(matches!(
+            client_error,
+            ClientError::PARAMS_IDS_OR_FILTERS_REQUIRED
+        ));
+    }
+
+    #[test]
+    fn test_client_error_rpc_code() {
+        let invalid_params = [
+            ClientError::LIST_OPTIONS_INVALID,
+            ClientError::BULK_OVER_MAX {
+                max: 1000,
+                actual: 1001,
+            },
+            ClientError::BULK_FILTER_EMPTY,
+            ClientError::PARAMS_IDS_OR_FILTERS_REQUIRED,
+        ];
+
+        for client_error in invalid_params {
+            assert_eq!(client_error.rpc_code(), -32602, "{client_error:?}");
+        }
+        assert_eq!(ClientError::SERVICE_ERROR.rpc_code(), -32603);
+        assert_eq!(ClientError::NO_AUTH.rpc_code(), -32000);
+    }
+}
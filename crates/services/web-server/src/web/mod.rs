@@ -4,6 +4,7 @@ pub mod mw_resp_map;
 pub mod routes_login;
 pub mod routes_rpc;
 pub mod routes_static;
+pub mod routes_ws;
 
 use lib_auth::token::generate_web_token;
 use tower_cookies::{Cookie, Cookies};
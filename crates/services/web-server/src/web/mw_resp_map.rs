@@ -4,11 +4,11 @@ use super::routes_rpc::RpcInfo;
 use axum::http::{Method, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::{json, to_value};
+use serde_json::{json, to_value, Value};
 use uuid::Uuid;
 
 use crate::log;
-use crate::web::Error;
+use crate::web::{ClientError, Error};
 
 use super::mw_auth::CtxW;
 
@@ -36,21 +36,8 @@ pub async fn mw_response_mapper(
     let client_status_error = web_error.map(Error::client_status_and_error);
 
     let error_response = client_status_error.as_ref().map(|(st_code, cl_err)| {
-        let client_error = to_value(cl_err).ok();
-        let message = client_error.as_ref().and_then(|v| v.get("message"));
-        let detail = client_error.as_ref().and_then(|v| v.get("detail"));
-
-        let client_error_body = json!({
-            "id": rpc_info.as_ref().map(|rpc| rpc.id.clone()),
-            "error": {
-                "message": message, // Variant name
-                "data": {
-                    "req_uuid": uuid.to_string(),
-                    "detail": detail,
-                }
-            }
-        });
-        tracing::debug!("CLIENT ERROR BODY: {client_error_body}");
+        let rpc_id = rpc_info.and_then(|rpc| rpc.id.clone());
+        let client_error_body = client_error_body(uuid, rpc_id, cl_err);
         (*st_code, Json(client_error_body)).into_response()
     });
 
@@ -73,3 +60,38 @@ pub async fn mw_response_mapper(
     // or just returns unmodified response.
     error_response.unwrap_or(res)
 }
+
+/// Returns the JSON-RPC 2.0 success body of the `result`.
+/// (For all the RPC responses: `/rpc`, single or batch, and `/rpc/ws`)
+pub fn rpc_result_body(rpc_id: Option<Value>, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": rpc_id,
+        "result": result,
+    })
+}
+
+/// Returns the JSON-RPC 2.0 error body of the `client_error`, for the request of the `uuid`.
+/// (For all the error responses, RPC or not)
+pub fn client_error_body(uuid: Uuid, rpc_id: Option<Value>, client_error: &ClientError) -> Value {
+    let code = client_error.rpc_code();
+    let client_error = to_value(client_error).ok();
+    let message = client_error.as_ref().and_then(|v| v.get("message"));
+    let detail = client_error.as_ref().and_then(|v| v.get("detail"));
+
+    let client_error_body = json!({
+        "jsonrpc": "2.0",
+        "id": rpc_id,
+        "error": {
+            "code": code,
+            "message": message, // Variant name
+            "data": {
+                "req_uuid": uuid.to_string(),
+                "detail": detail,
+            }
+        }
+    });
+    tracing::debug!("CLIENT ERROR BODY: {client_error_body}");
+
+    client_error_body
+}
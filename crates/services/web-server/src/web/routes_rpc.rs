@@ -1,16 +1,37 @@
 use axum::{
-    extract::State,
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    extract::{OriginalUri, State},
+    http::{Method, StatusCode, Uri},
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
+use lib_core::ctx::Ctx;
 use lib_core::model::ModelManager;
 use lib_rpc::router::RpcRouter;
-use lib_rpc::{task_rpc, RpcRequest, RpcResources};
-use serde_json::{json, Value};
+use lib_rpc::{comment_rpc, label_rpc, project_rpc, task_rpc, RpcRequest, RpcResources};
+use serde_json::Value;
 use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::log;
 
 use super::mw_auth::CtxW;
+use super::mw_resp_map::{client_error_body, rpc_result_body};
+use super::{ClientError, Error};
+
+/// The max number of concurrent calls of a `/rpc/ws` socket
+/// (the next requests wait for one of them to complete).
+const WS_CALLS_MAX: usize = 16;
+
+/// The max number of calls of a `/rpc` batch.
+const BATCH_CALLS_MAX: usize = 50;
+
+/// The max number of concurrent calls of a `/rpc` batch
+/// (below the db pool size, so a batch does not starve the other requests).
+const BATCH_CALLS_CONCURRENT_MAX: usize = 4;
 
 /// The `RpcState` is the Axum State that will be used for the Axum RPC router handler.
 #[derive(Clone)]
@@ -25,19 +46,39 @@ pub struct RpcInfo {
 }
 
 pub fn routes(rpc_state: RpcState) -> Router {
-    let rpc_router = RpcRouter::init().extend(task_rpc::rpc_router());
+    let rpc_router = RpcRouter::init()
+        .extend(project_rpc::rpc_router())
+        .extend(label_rpc::rpc_router())
+        .extend(comment_rpc::rpc_router())
+        .extend(task_rpc::rpc_router());
 
     Router::new()
         .route("/rpc", post(rpc_axum_handler))
+        .route("/rpc/ws", get(rpc_ws_handler))
         .with_state((rpc_state, Arc::new(rpc_router)))
 }
 
+/// Serves a JSON-RPC request, or a batch of them (JSON array, see `rpc_batch`).
 async fn rpc_axum_handler(
     State((rpc_state, rpc_router)): State<(RpcState, Arc<RpcRouter>)>,
+    OriginalUri(uri): OriginalUri,
     ctx: CtxW,
-    Json(rpc_req): Json<RpcRequest>,
+    Json(rpc_req): Json<Value>,
 ) -> Response {
     let ctx = ctx.0;
+    let rpc_req = match rpc_req {
+        Value::Array(rpc_reqs) => {
+            return rpc_batch(rpc_state.mm, rpc_router, ctx, uri, rpc_reqs).await;
+        }
+        rpc_req => match serde_json::from_value::<RpcRequest>(rpc_req) {
+            Ok(rpc_req) => rpc_req,
+            Err(ex) => {
+                let reason = ex.to_string();
+                return Error::RpcRequestInvalid { reason }.into_response();
+            }
+        },
+    };
+
     // Create the RPC Info to be set to the response extensions.
     let rpc_info = RpcInfo {
         id: rpc_req.id.clone(),
@@ -46,22 +87,13 @@ async fn rpc_axum_handler(
 
     let rpc_method = &rpc_info.method;
     let rpc_params = rpc_req.params;
-    let rpc_resources = RpcResources {
-        ctx: Some(ctx),
-        mm: rpc_state.mm,
-    };
+    let rpc_resources = RpcResources::new(rpc_state.mm, Some(ctx));
 
     // Exec Rpc Route
     let res = rpc_router.call(rpc_method, rpc_resources, rpc_params).await;
 
     // Build RPC Success Response
-    let res = res.map(|v| {
-        let body_res = json!({
-            "id": rpc_info.id,
-            "result": v,
-        });
-        Json(body_res)
-    });
+    let res = res.map(|v| Json(rpc_result_body(rpc_info.id.clone(), v)));
 
     // Create and update Axum Response
     let res = res.map_err(crate::web::Error::from);
@@ -70,3 +102,198 @@ async fn rpc_axum_handler(
 
     res
 }
+
+/// Executes the calls of the batch concurrently, and returns the array of their responses
+/// (see `exec_rpc`), in the order of the requests.
+///
+/// - Each call is logged on its own. The notifications (calls without an `id`) are not
+///   answered, unless invalid, and a batch of notifications only gets a `204 No Content`.
+/// - The calls run concurrently (up to `BATCH_CALLS_CONCURRENT_MAX` at a time), so a call may
+///   not see the changes of the previous ones.
+/// - The calls are independent (own `RpcResources`), so a failed call does not roll back the
+///   other ones.
+/// - An empty batch, or one of more than `BATCH_CALLS_MAX` calls, is rejected as a whole.
+async fn rpc_batch(
+    mm: ModelManager,
+    rpc_router: Arc<RpcRouter>,
+    ctx: Ctx,
+    uri: Uri,
+    rpc_reqs: Vec<Value>,
+) -> Response {
+    if rpc_reqs.is_empty() || rpc_reqs.len() > BATCH_CALLS_MAX {
+        let reason = format!("batch must have from 1 to {BATCH_CALLS_MAX} requests");
+        return Error::RpcRequestInvalid { reason }.into_response();
+    }
+
+    let slots = Arc::new(Semaphore::new(BATCH_CALLS_CONCURRENT_MAX));
+    let calls: Vec<_> = rpc_reqs
+        .into_iter()
+        .map(|rpc_req| {
+            let (mm, rpc_router, ctx, uri, slots) = (
+                mm.clone(),
+                rpc_router.clone(),
+                ctx.clone(),
+                uri.clone(),
+                slots.clone(),
+            );
+            tokio::spawn(async move {
+                // NOTE: The semaphore is never closed.
+                let _slot = slots.acquire_owned().await.ok();
+                let notification = rpc_req.as_object().is_some_and(|o| !o.contains_key("id"));
+                let (res_body, client_error) =
+                    exec_rpc(&rpc_router, mm, ctx, (Method::POST, uri), Ok(rpc_req)).await;
+                let answered = !notification
+                    || matches!(client_error, Some(ClientError::RPC_REQUEST_INVALID { .. }));
+                answered.then_some(res_body)
+            })
+        })
+        .collect();
+
+    let mut res_bodies = Vec::with_capacity(calls.len());
+    for call in calls {
+        match call.await {
+            Ok(res_body) => res_bodies.extend(res_body),
+            // NOTE: A panicking call fails the batch (as it would fail a single request).
+            Err(ex) => {
+                tracing::error!("{:<12} - rpc batch call - {ex}", "HANDLER");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    if res_bodies.is_empty() {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+    Json(res_bodies).into_response()
+}
+
+/// Serves the JSON-RPC calls over a WebSocket: each text frame is an `RpcRequest`, answered by
+/// a text frame with the same body as the `/rpc` one (`result` or `error`) and the same `id`.
+///
+/// - The `Ctx` is resolved once, from the `auth-token` cookie of the upgrade request.
+/// - The calls run concurrently, so the responses can come in a different order than the
+///   requests, and the clients must match them by `id`.
+async fn rpc_ws_handler(
+    State((rpc_state, rpc_router)): State<(RpcState, Arc<RpcRouter>)>,
+    OriginalUri(uri): OriginalUri,
+    ctx: CtxW,
+    ws: WebSocketUpgrade,
+) -> Response {
+    debug!("{:<12} - rpc_ws_handler", "HANDLER");
+
+    ws.on_upgrade(move |socket| handle_rpc_socket(socket, rpc_state.mm, rpc_router, ctx.0, uri))
+}
+
+/// Serves the socket until it is closed by the client or a send fails.
+/// (The calls in flight then still complete, their responses being dropped)
+async fn handle_rpc_socket(
+    mut socket: WebSocket,
+    mm: ModelManager,
+    rpc_router: Arc<RpcRouter>,
+    ctx: Ctx,
+    uri: Uri,
+) {
+    let (res_tx, mut res_rx) = mpsc::unbounded_channel::<Value>();
+    let calls = Arc::new(Semaphore::new(WS_CALLS_MAX));
+
+    loop {
+        tokio::select! {
+            client_msg = socket.recv() => {
+                let text = match client_msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Ping / pong are answered by axum, and binary frames are ignored.
+                    Some(Ok(_)) => continue,
+                };
+                let Ok(call) = calls.clone().acquire_owned().await else {
+                    break;
+                };
+
+                let (mm, rpc_router, ctx, uri, res_tx) =
+                    (mm.clone(), rpc_router.clone(), ctx.clone(), uri.clone(), res_tx.clone());
+                tokio::spawn(async move {
+                    let rpc_req = serde_json::from_str::<Value>(&text).map_err(|ex| ex.to_string());
+                    let (res_body, _) = exec_rpc(&rpc_router, mm, ctx, (Method::GET, uri), rpc_req).await;
+                    let _ = res_tx.send(res_body);
+                    drop(call);
+                });
+            }
+            // NOTE: Never `None`, the loop holding a `res_tx`.
+            Some(res_body) = res_rx.recv() => {
+                if socket.send(Message::Text(res_body.to_string())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!(
+        "{:<12} - rpc ws closed for user {}",
+        "HANDLER",
+        ctx.user_id()
+    );
+}
+
+/// Executes the JSON `rpc_req` (or its parsing error) of the `http` request, logs it, and
+/// returns its response body (see `rpc_result_body` and `client_error_body`), and the client
+/// error.
+/// (For the calls answered outside of the `mw_response_mapper`, i.e., the WebSocket and batch ones)
+async fn exec_rpc(
+    rpc_router: &RpcRouter,
+    mm: ModelManager,
+    ctx: Ctx,
+    (req_method, uri): (Method, Uri),
+    rpc_req: core::result::Result<Value, String>,
+) -> (Value, Option<ClientError>) {
+    let uuid = Uuid::new_v4();
+
+    // -- Parse the request (keeping its `id`, if any, for the error).
+    let (rpc_id, rpc_req) = match rpc_req {
+        Ok(rpc_req) => (
+            rpc_req.get("id").cloned(),
+            serde_json::from_value::<RpcRequest>(rpc_req).map_err(|ex| ex.to_string()),
+        ),
+        Err(reason) => (None, Err(reason)),
+    };
+
+    // -- Exec
+    let (rpc_info, res) = match rpc_req {
+        Ok(rpc_req) => {
+            let rpc_info = RpcInfo {
+                id: rpc_req.id,
+                method: rpc_req.method,
+            };
+            let rpc_resources = RpcResources::new(mm, Some(ctx.clone()));
+            let res = rpc_router
+                .call(&rpc_info.method, rpc_resources, rpc_req.params)
+                .await
+                .map_err(Error::from);
+            (Some(rpc_info), res)
+        }
+        Err(reason) => (None, Err(Error::RpcRequestInvalid { reason })),
+    };
+
+    // -- Build the response body.
+    let (res_body, web_error, client_error) = match res {
+        Ok(result) => (rpc_result_body(rpc_id, result), None, None),
+        Err(web_error) => {
+            let (_, client_error) = web_error.client_status_and_error();
+            let res_body = client_error_body(uuid, rpc_id, &client_error);
+            (res_body, Some(web_error), Some(client_error))
+        }
+    };
+
+    // TODO: Should handle errors
+    let _ = log::log_request(
+        uuid,
+        req_method,
+        uri,
+        rpc_info.as_ref(),
+        Some(CtxW(ctx)),
+        web_error.as_ref(),
+        client_error.clone(),
+    )
+    .await;
+
+    (res_body, client_error)
+}
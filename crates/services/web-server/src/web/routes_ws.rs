@@ -0,0 +1,172 @@
+//! The `/api/ws` WebSocket, pushing the domain events of the outbox to the clients.
+//!
+//! - The `Ctx` is resolved from the `auth-token` cookie of the upgrade request (`mw_ctx_require`).
+//! - The client subscribes to the changes of entities, and only receives the events of the rows
+//!   visible to its user (owner or assignee, see `Event::is_visible_to`).
+//! - The events are delivered at least once, so the same event (same `id`) can be pushed twice.
+//!
+//! Client messages:
+//! ```json
+//! {"type": "subscribe", "entities": ["task", "project"]}
+//! {"type": "unsubscribe", "entities": ["project"]}
+//! ```
+//!
+//! Server messages:
+//! ```json
+//! {"type": "subscribed", "entities": ["task"]}
+//! {"type": "event", "event": {"id": 1042, "name": "TaskUpdated", "entity": "task", ...}}
+//! {"type": "lagged", "skipped": 12}
+//! {"type": "error", "message": "..."}
+//! ```
+//! (`lagged` when the client was too slow and missed events, which it should then re-fetch)
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use lib_core::ctx::Ctx;
+use lib_core::model::outbox::Event;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::debug;
+
+use super::mw_auth::CtxW;
+
+/// The number of events buffered for each socket before it lags.
+const EVENTS_CAPACITY: usize = 1024;
+
+/// The entities the clients can subscribe to.
+const ENTITIES: &[&str] = &["task", "project"];
+
+/// The `WsState` is the Axum State of the WebSocket route, broadcasting the events to the
+/// sockets.
+#[derive(Clone)]
+pub struct WsState {
+    events_tx: broadcast::Sender<Arc<Event>>,
+}
+
+impl WsState {
+    pub fn new() -> Self {
+        let (events_tx, _) = broadcast::channel(EVENTS_CAPACITY);
+        WsState { events_tx }
+    }
+
+    /// Pushes the event to the open sockets (dropped when there are none).
+    pub fn publish(&self, event: Arc<Event>) {
+        let _ = self.events_tx.send(event);
+    }
+}
+
+impl Default for WsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The messages of the client.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMsg {
+    Subscribe { entities: Vec<String> },
+    Unsubscribe { entities: Vec<String> },
+}
+
+/// The messages pushed to the client.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMsg<'a> {
+    Subscribed { entities: &'a BTreeSet<String> },
+    Event { event: &'a Event },
+    Lagged { skipped: u64 },
+    Error { message: String },
+}
+
+pub fn routes(ws_state: WsState) -> Router {
+    Router::new()
+        .route("/ws", get(ws_axum_handler))
+        .with_state(ws_state)
+}
+
+async fn ws_axum_handler(
+    State(ws_state): State<WsState>,
+    ctx: CtxW,
+    ws: WebSocketUpgrade,
+) -> Response {
+    debug!("{:<12} - ws_axum_handler", "HANDLER");
+
+    let events_rx = ws_state.events_tx.subscribe();
+    ws.on_upgrade(move |socket| handle_socket(socket, ctx.0, events_rx))
+}
+
+/// Serves the socket until it is closed by the client or a send fails.
+async fn handle_socket(
+    mut socket: WebSocket,
+    ctx: Ctx,
+    mut events_rx: broadcast::Receiver<Arc<Event>>,
+) {
+    let mut entities: BTreeSet<String> = BTreeSet::new();
+
+    loop {
+        let msg = tokio::select! {
+            client_msg = socket.recv() => match client_msg {
+                Some(Ok(Message::Text(text))) => on_client_msg(&mut entities, &text),
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Ping / pong are answered by axum, and binary frames are ignored.
+                Some(Ok(_)) => continue,
+            },
+            event = events_rx.recv() => match event {
+                Ok(event) if entities.contains(&event.entity) && event.is_visible_to(&ctx) => {
+                    to_text(&ServerMsg::Event { event: &event })
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => to_text(&ServerMsg::Lagged { skipped }),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        if socket.send(Message::Text(msg)).await.is_err() {
+            break;
+        }
+    }
+
+    debug!("{:<12} - ws closed for user {}", "HANDLER", ctx.user_id());
+}
+
+/// Applies the client message to the subscribed `entities`, and returns the response.
+fn on_client_msg(entities: &mut BTreeSet<String>, text: &str) -> String {
+    let client_msg = match serde_json::from_str::<ClientMsg>(text) {
+        Ok(client_msg) => client_msg,
+        Err(ex) => {
+            return to_text(&ServerMsg::Error {
+                message: format!("invalid message - {ex}"),
+            })
+        }
+    };
+
+    match client_msg {
+        ClientMsg::Subscribe { entities: names } => {
+            if let Some(name) = names.iter().find(|name| !ENTITIES.contains(&name.as_str())) {
+                return to_text(&ServerMsg::Error {
+                    message: format!("unknown entity '{name}' (expected one of {ENTITIES:?})"),
+                });
+            }
+            entities.extend(names);
+        }
+        ClientMsg::Unsubscribe { entities: names } => {
+            for name in names {
+                entities.remove(&name);
+            }
+        }
+    }
+
+    to_text(&ServerMsg::Subscribed { entities })
+}
+
+fn to_text(server_msg: &ServerMsg) -> String {
+    // NOTE: The server messages always serialize (string keys only).
+    serde_json::to_string(server_msg).unwrap_or_default()
+}
@@ -0,0 +1,54 @@
+//! Applies, reverts or lists the db migrations of `SERVICE_DB_MIGRATIONS_DIR`
+//! on the `SERVICE_DB_URL` database.
+//!
+//! Usage: `cargo run -p db-migrate -- <up | down [steps] | status>`
+
+use anyhow::{bail, Result};
+use lib_core::model::migrate::Migrator;
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "usage: db-migrate <up | down [steps] | status>";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .without_time()
+        .with_target(false)
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let migrator = Migrator::init().await?;
+
+    match args.as_slice() {
+        ["up"] => {
+            let versions = migrator.up().await?;
+            println!("\nApplied migrations: {versions:?}");
+        }
+        ["down"] | ["down", _] => {
+            let steps = match args.get(1) {
+                Some(steps) => steps.parse()?,
+                None => 1,
+            };
+            let versions = migrator.down(steps).await?;
+            println!("\nReverted migrations: {versions:?}");
+        }
+        ["status"] => {
+            println!();
+            for status in migrator.status().await? {
+                let applied_at = status.applied_at.map(|t| t.to_string()).unwrap_or_default();
+                println!(
+                    "{:04}_{:<32} {:<12} {applied_at}",
+                    status.version,
+                    status.name,
+                    format!("{:?}", status.state)
+                );
+            }
+        }
+        _ => bail!(USAGE),
+    }
+
+    Ok(())
+}
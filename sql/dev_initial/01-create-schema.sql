@@ -1,33 +0,0 @@
--- Base app schema
-
--- User
-CREATE TABLE user (
-  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
-  username VARCHAR(128) NOT NULL UNIQUE,
-
-  -- Auth 
-  pwd VARCHAR(256),
-  pwd_salt UUID NOT NULL DEFAULT gen_random_uuid(),
-  token_salt UUID NOT NULL DEFAULT gen_random_uuid()
-);
-
--- -- Project
--- CREATE TABLE project (
---   id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
---
---   -- Properties
---   owner_id BIGINT NOT NULL,
---   name varchar(256) NOT NULL,
--- );
-
--- Task
-CREATE TABLE task (
-  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
-
-  -- project_id BIGINT NOT NULL,
-
-  -- Properties
-  title VARCHAR(256) NOT NULL,
-  done BOOL NOT NULL DEFAULT false
-);
-
@@ -0,0 +1,2 @@
+-- User demo1
+INSERT INTO "user" (username, cid, ctime, mid, mtime) VALUES ( 'demo1', 0, now(), 0, now() )
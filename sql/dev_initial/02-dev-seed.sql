@@ -1,2 +0,0 @@
--- User demo1
-INSERT INTO "user" (username) VALUES ( 'demo1' )
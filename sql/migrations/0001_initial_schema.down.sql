@@ -0,0 +1,5 @@
+-- Initial app schema
+
+DROP TABLE IF EXISTS task;
+DROP TABLE IF EXISTS project;
+DROP TABLE IF EXISTS "user";
@@ -0,0 +1,70 @@
+-- Base app schema
+
+-- User
+CREATE TABLE "user" (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+  username VARCHAR(128) NOT NULL UNIQUE,
+
+  -- Auth 
+  pwd VARCHAR(256),
+  pwd_salt UUID NOT NULL DEFAULT gen_random_uuid(),
+  token_salt UUID NOT NULL DEFAULT gen_random_uuid(),
+
+  -- Timestamps
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL,
+  mid BIGINT NOT NULL,
+  mtime TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Project
+CREATE TABLE project (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- Properties
+  owner_id BIGINT NOT NULL,
+  name VARCHAR(256) NOT NULL,
+
+  -- Optimistic concurrency
+  version BIGINT NOT NULL DEFAULT 0,
+
+  -- Timestamps
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL,
+  mid BIGINT NOT NULL,
+  mtime TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_project_owner_id ON project(owner_id);
+
+-- Task
+CREATE TABLE task (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- FK
+  project_id BIGINT NOT NULL,
+
+  -- Properties
+  owner_id BIGINT NOT NULL,
+  title VARCHAR(256) NOT NULL,
+  done BOOL NOT NULL DEFAULT false,
+
+  -- Optimistic concurrency
+  version BIGINT NOT NULL DEFAULT 0,
+
+  -- Timestamps
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL,
+  mid BIGINT NOT NULL,
+  mtime TIMESTAMP WITH TIME ZONE NOT NULL,
+
+  -- Soft delete
+  deleted_at TIMESTAMP WITH TIME ZONE
+);
+
+ALTER TABLE task ADD CONSTRAINT fk_project
+  FOREIGN KEY (project_id) REFERENCES project(id)
+  ON DELETE RESTRICT;
+
+CREATE INDEX idx_task_project_id ON task(project_id);
+CREATE INDEX idx_task_owner_id ON task(owner_id);
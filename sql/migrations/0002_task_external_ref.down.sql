@@ -0,0 +1,4 @@
+-- Task external reference
+
+ALTER TABLE task DROP CONSTRAINT IF EXISTS uq_task_owner_id_external_ref;
+ALTER TABLE task DROP COLUMN IF EXISTS external_ref;
@@ -0,0 +1,7 @@
+-- Task external reference (e.g., the id of the task in an external tracker)
+
+ALTER TABLE task ADD COLUMN external_ref VARCHAR(256);
+
+-- Natural key of the task upserts (NULL `external_ref` are not unique).
+ALTER TABLE task ADD CONSTRAINT uq_task_owner_id_external_ref
+  UNIQUE (owner_id, external_ref);
@@ -0,0 +1,4 @@
+-- Task full-text search
+
+DROP INDEX IF EXISTS idx_task_search_vector;
+ALTER TABLE task DROP COLUMN IF EXISTS search_vector;
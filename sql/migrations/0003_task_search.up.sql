@@ -0,0 +1,6 @@
+-- Task full-text search
+
+ALTER TABLE task ADD COLUMN search_vector tsvector
+  GENERATED ALWAYS AS (to_tsvector('english', title)) STORED;
+
+CREATE INDEX idx_task_search_vector ON task USING GIN (search_vector);
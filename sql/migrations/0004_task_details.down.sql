@@ -0,0 +1,19 @@
+-- Task details
+
+DROP TRIGGER IF EXISTS task_set_done_at ON task;
+DROP FUNCTION IF EXISTS task_set_done_at();
+
+DROP INDEX IF EXISTS idx_task_search_vector;
+ALTER TABLE task DROP COLUMN IF EXISTS search_vector;
+ALTER TABLE task ADD COLUMN search_vector tsvector
+  GENERATED ALWAYS AS (to_tsvector('english', title)) STORED;
+CREATE INDEX idx_task_search_vector ON task USING GIN (search_vector);
+
+DROP INDEX IF EXISTS idx_task_due_date;
+ALTER TABLE task
+  DROP COLUMN IF EXISTS description,
+  DROP COLUMN IF EXISTS due_date,
+  DROP COLUMN IF EXISTS priority,
+  DROP COLUMN IF EXISTS done_at;
+
+DROP TYPE IF EXISTS task_priority;
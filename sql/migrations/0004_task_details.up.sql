@@ -0,0 +1,35 @@
+-- Task details: description, due date, priority and completion time
+
+CREATE TYPE task_priority AS ENUM ('Low', 'Medium', 'High', 'Urgent');
+
+ALTER TABLE task
+  ADD COLUMN description TEXT,
+  ADD COLUMN due_date DATE,
+  ADD COLUMN priority task_priority NOT NULL DEFAULT 'Medium',
+  ADD COLUMN done_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_task_due_date ON task(due_date);
+
+-- The searchable text now includes the description.
+-- NOTE: Must match `TaskBmc::search_document()`.
+DROP INDEX idx_task_search_vector;
+ALTER TABLE task DROP COLUMN search_vector;
+ALTER TABLE task ADD COLUMN search_vector tsvector
+  GENERATED ALWAYS AS (to_tsvector('english', title || ' ' || coalesce(description, ''))) STORED;
+CREATE INDEX idx_task_search_vector ON task USING GIN (search_vector);
+
+-- `done_at` is set when `done` becomes true, and cleared when it becomes false.
+CREATE FUNCTION task_set_done_at() RETURNS trigger AS $$
+BEGIN
+  IF NEW.done AND (TG_OP = 'INSERT' OR NOT OLD.done) THEN
+    NEW.done_at := now();
+  ELSIF NOT NEW.done THEN
+    NEW.done_at := NULL;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_set_done_at
+  BEFORE INSERT OR UPDATE OF done ON task
+  FOR EACH ROW EXECUTE FUNCTION task_set_done_at();
@@ -0,0 +1,26 @@
+-- Labels, and their many-to-many relation to the tasks
+
+CREATE TABLE label (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- Properties
+  owner_id BIGINT NOT NULL,
+  name VARCHAR(128) NOT NULL,
+
+  -- Timestamps
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL,
+  mid BIGINT NOT NULL,
+  mtime TIMESTAMP WITH TIME ZONE NOT NULL,
+
+  CONSTRAINT uq_label_owner_id_name UNIQUE (owner_id, name)
+);
+
+CREATE TABLE task_label (
+  task_id BIGINT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+  label_id BIGINT NOT NULL REFERENCES label(id) ON DELETE CASCADE,
+
+  PRIMARY KEY (task_id, label_id)
+);
+
+CREATE INDEX idx_task_label_label_id ON task_label(label_id);
@@ -0,0 +1,3 @@
+-- Task comments
+
+DROP TABLE IF EXISTS comment;
@@ -0,0 +1,20 @@
+-- Task comments
+
+CREATE TABLE comment (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- FK
+  task_id BIGINT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+
+  -- Properties
+  author_id BIGINT NOT NULL,
+  content TEXT NOT NULL,
+
+  -- Timestamps
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL,
+  mid BIGINT NOT NULL,
+  mtime TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_comment_task_id ON comment(task_id);
@@ -0,0 +1,4 @@
+-- Task assignee
+
+DROP INDEX IF EXISTS idx_task_assignee_id;
+ALTER TABLE task DROP COLUMN IF EXISTS assignee_id;
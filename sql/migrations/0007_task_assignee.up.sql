@@ -0,0 +1,6 @@
+-- Task assignee
+
+ALTER TABLE task ADD COLUMN assignee_id BIGINT
+  REFERENCES "user"(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_task_assignee_id ON task(assignee_id);
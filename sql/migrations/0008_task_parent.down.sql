@@ -0,0 +1,4 @@
+-- Task hierarchy (subtasks)
+
+DROP INDEX IF EXISTS idx_task_parent_id;
+ALTER TABLE task DROP COLUMN IF EXISTS parent_id;
@@ -0,0 +1,6 @@
+-- Task hierarchy (subtasks)
+
+ALTER TABLE task ADD COLUMN parent_id BIGINT
+  REFERENCES task(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_task_parent_id ON task(parent_id);
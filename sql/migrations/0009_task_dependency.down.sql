@@ -0,0 +1,9 @@
+-- Task dependencies
+
+DROP TRIGGER IF EXISTS task_blocker_refresh_blocked ON task;
+DROP FUNCTION IF EXISTS task_blocker_refresh_blocked();
+DROP TABLE IF EXISTS task_dependency;
+DROP FUNCTION IF EXISTS task_dependency_refresh_blocked();
+DROP FUNCTION IF EXISTS task_refresh_blocked(BIGINT[]);
+
+ALTER TABLE task DROP COLUMN IF EXISTS blocked;
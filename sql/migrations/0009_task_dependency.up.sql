@@ -0,0 +1,56 @@
+-- Task dependencies ("task is blocked by blocker")
+
+CREATE TABLE task_dependency (
+  task_id BIGINT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+  blocker_id BIGINT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+
+  PRIMARY KEY (task_id, blocker_id),
+  CONSTRAINT ck_task_dependency_not_self CHECK (task_id <> blocker_id)
+);
+
+CREATE INDEX idx_task_dependency_blocker_id ON task_dependency(blocker_id);
+
+-- `true` if one of the live blockers of the task is not done (maintained by the triggers below).
+ALTER TABLE task ADD COLUMN blocked BOOL NOT NULL DEFAULT false;
+
+CREATE FUNCTION task_refresh_blocked(task_ids BIGINT[]) RETURNS void AS $$
+BEGIN
+  UPDATE task SET blocked = EXISTS (
+    SELECT 1 FROM task_dependency d JOIN task b ON b.id = d.blocker_id
+    WHERE d.task_id = task.id AND NOT b.done AND b.deleted_at IS NULL
+  )
+  WHERE id = ANY(task_ids);
+END;
+$$ LANGUAGE plpgsql;
+
+-- When a dependency is added or removed.
+CREATE FUNCTION task_dependency_refresh_blocked() RETURNS trigger AS $$
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    PERFORM task_refresh_blocked(ARRAY[OLD.task_id]);
+  ELSE
+    PERFORM task_refresh_blocked(ARRAY[NEW.task_id]);
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_dependency_refresh_blocked
+  AFTER INSERT OR DELETE ON task_dependency
+  FOR EACH ROW EXECUTE FUNCTION task_dependency_refresh_blocked();
+
+-- When a blocker is done / undone, or moved to / restored from the trash.
+CREATE FUNCTION task_blocker_refresh_blocked() RETURNS trigger AS $$
+BEGIN
+  PERFORM task_refresh_blocked(
+    ARRAY(SELECT task_id FROM task_dependency WHERE blocker_id = NEW.id)
+  );
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_blocker_refresh_blocked
+  AFTER UPDATE OF done, deleted_at ON task
+  FOR EACH ROW
+  WHEN (OLD.done IS DISTINCT FROM NEW.done OR OLD.deleted_at IS DISTINCT FROM NEW.deleted_at)
+  EXECUTE FUNCTION task_blocker_refresh_blocked();
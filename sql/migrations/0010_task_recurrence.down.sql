@@ -0,0 +1,5 @@
+-- Recurring tasks
+
+ALTER TABLE task
+  DROP COLUMN IF EXISTS recurrence,
+  DROP COLUMN IF EXISTS next_occurrence_id;
@@ -0,0 +1,7 @@
+-- Recurring tasks
+
+ALTER TABLE task
+  -- The recurrence rule (normalized, e.g., 'FREQ=WEEKLY;INTERVAL=2').
+  ADD COLUMN recurrence VARCHAR(128),
+  -- The next occurrence, created when the task was done.
+  ADD COLUMN next_occurrence_id BIGINT REFERENCES task(id) ON DELETE SET NULL;
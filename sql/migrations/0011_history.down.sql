@@ -0,0 +1,3 @@
+-- Change history of the entities
+
+DROP TABLE IF EXISTS history;
@@ -0,0 +1,22 @@
+-- Change history of the entities (see `DbBmc::has_history`)
+
+CREATE TABLE history (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- The changed entity (no FK, the history outlives the entity)
+  entity VARCHAR(64) NOT NULL,
+  entity_id BIGINT NOT NULL,
+
+  -- Properties
+  -- 'update', 'delete', 'restore' or 'purge'
+  op VARCHAR(16) NOT NULL,
+  -- The changed columns, with their values before and after the change
+  before JSONB NOT NULL,
+  after JSONB NOT NULL,
+
+  -- Timestamps (the user and time of the change)
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_history_entity_entity_id ON history(entity, entity_id);
@@ -0,0 +1,3 @@
+-- Transactional outbox of the domain events
+
+DROP TABLE IF EXISTS outbox;
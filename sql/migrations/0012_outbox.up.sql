@@ -0,0 +1,27 @@
+-- Transactional outbox of the domain events (see `model::outbox`)
+
+CREATE TABLE outbox (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  -- Properties
+  -- The event name, e.g., 'TaskCreated'
+  name VARCHAR(128) NOT NULL,
+  -- The changed entity (no FK, the events outlive the entity)
+  entity VARCHAR(64) NOT NULL,
+  entity_id BIGINT NOT NULL,
+  -- The entity row after the change (before it for a deleted row)
+  data JSONB NOT NULL,
+  -- The entity row before the change (none for a created row)
+  before JSONB,
+
+  -- Delivery
+  processed_at TIMESTAMP WITH TIME ZONE,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  last_error TEXT,
+
+  -- Timestamps (the user and time of the change)
+  cid BIGINT NOT NULL,
+  ctime TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_outbox_pending ON outbox(id) WHERE processed_at IS NULL;